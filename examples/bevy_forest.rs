@@ -2,7 +2,7 @@ use bevy::{
     pbr::wireframe::{Wireframe, WireframePlugin},
     prelude::*, utils::HashMap,
 };
-use hyperion::{grammar::Token, LSystem, LSystemBuilder, Value};
+use hyperion::{grammar::Token, LSystem, LSystemBuilder, State, Value};
 use rand::Rng;
 
 mod mesh;
@@ -52,8 +52,10 @@ fn setup_forest(
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let monopodial = monopodial().unwrap();
+    let mono_generations: Vec<State<Token>> = monopodial.generations().take(12).collect::<Result<_, _>>().unwrap();
     let mono_material = materials.add(Color::from(Srgba::GREEN));
     let sympodial = sympodial().unwrap();
+    let sym_generations: Vec<State<Token>> = sympodial.generations().take(12).collect::<Result<_, _>>().unwrap();
     let sym_material = materials.add(Color::from(Srgba::BLUE));
 
     let mut change = false;
@@ -72,7 +74,8 @@ fn setup_forest(
             if mesh_cache.contains_key(&(generation, Species::Mono)) {
                 (mesh_cache.get(&(generation, Species::Mono)).unwrap().clone(), mono_material.clone())
             } else {
-                let _mono_mesh = mesh::Renderer::new(&monopodial, default()).build(generation);
+                let _mono_mesh = mesh::Renderer::new(default())
+                    .build_state(mono_generations[generation].clone());
                 let mono_mesh = meshes.add(_mono_mesh);
                 mesh_cache.insert((generation, Species::Mono), mono_mesh.clone());
                 (mono_mesh, mono_material.clone())
@@ -83,7 +86,8 @@ fn setup_forest(
             if mesh_cache.contains_key(&(generation, Species::Sym)) {
                 (mesh_cache.get(&(generation, Species::Sym)).unwrap().clone(), sym_material.clone())
             } else {
-                let _sym_mesh = mesh::Renderer::new(&sympodial, default()).build(generation);
+                let _sym_mesh = mesh::Renderer::new(default())
+                    .build_state(sym_generations[generation].clone());
                 let sym_mesh = meshes.add(_sym_mesh);
                 mesh_cache.insert((generation, Species::Sym), sym_mesh.clone());
                 (sym_mesh, sym_material.clone())
@@ -1,22 +1,20 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
 
-use hyperion::{grammar::Token, LSystem, Module};
+use hyperion::{grammar::Token, Module, State};
 
 use super::{RenderConfig, RenderState};
 
-pub struct Renderer<'a> {
+pub struct Renderer {
     data: super::MeshData,
-    lsys: &'a LSystem<Token>,
     cfg: RenderConfig,
     state: RenderState,
     last_state: (u32, RenderState),
 }
 
-impl<'a> Renderer<'a> {
-    pub fn new(lsys: &'a LSystem<Token>, cfg: RenderConfig) -> Renderer<'a> {
+impl Renderer {
+    pub fn new(cfg: RenderConfig) -> Renderer {
         Renderer {
-            lsys,
             data: Default::default(),
             last_state: (0, (&cfg).into()),
             state: (&cfg).into(),
@@ -24,7 +22,7 @@ impl<'a> Renderer<'a> {
         }
     }
 
-    pub fn build(mut self, generation: usize) -> Mesh {
+    pub fn build_state(mut self, tokens: State<Token>) -> Mesh {
         let mut locations = HashMap::new();
 
         let mut meshes = vec![];
@@ -34,7 +32,6 @@ impl<'a> Renderer<'a> {
         let mut stack = vec![];
         self.last_state = (0, (&self.cfg).into());
         self.state = (&self.cfg).into();
-        let tokens = self.lsys.sample(generation);
         for token in tokens {
             match &token.token {
                 Token::F => {
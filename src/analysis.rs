@@ -0,0 +1,208 @@
+//! Growth analysis of deterministic context free LSystems.
+
+use crate::{lsystem::Productions, Alphabet, LSystem, Unsupported};
+
+/// Maximum number of power iterations used to find an eigenvalue.
+const MAX_ITERATIONS: usize = 10_000;
+
+/// How the number of modules grows with the generation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Growth {
+    /// The number of modules never exceeds some bound.
+    Bounded,
+    /// The number of modules grows like `n^d`.
+    Polynomial(usize),
+    /// The number of modules grows like `λ^n`.
+    Exponential(f64),
+}
+
+/// The result of analysing the productions of an LSystem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis<A: Alphabet> {
+    /// Every token in the axiom and rules, in order of first appearance.
+    pub symbols: Vec<A>,
+
+    /// The production matrix, where entry `[i][j]` counts `symbols[j]` in the successor
+    /// of `symbols[i]`.
+    pub matrix: Vec<Vec<u128>>,
+
+    /// How the number of modules derived from the axiom grows.
+    pub growth: Growth,
+
+    /// The dominant eigenvalue of the production matrix.
+    pub eigenvalue: f64,
+
+    /// Tokens whose expansion eventually vanishes.
+    pub dead: Vec<A>,
+
+    /// Tokens that never appear in any generation.
+    pub unreachable: Vec<A>,
+}
+
+impl<A: Alphabet> Analysis<A> {
+
+    /// Analyse an LSystem with at most one rule per token and no contexts or conditions.
+    pub fn new(lsystem: &LSystem<A>) -> Result<Self, Unsupported> {
+        let productions = Productions::new(lsystem)?;
+        let matrix = productions.matrix();
+        let size = productions.symbols.len();
+
+        // reach[i][j] is set when symbol j appears in some expansion of symbol i.
+        let mut reach = vec![vec![false; size]; size];
+        for (from, row) in reach.iter_mut().enumerate() {
+            let mut stack = productions.successors[from].clone();
+            while let Some(x) = stack.pop() {
+                if !row[x] {
+                    row[x] = true;
+                    stack.extend(&productions.successors[x]);
+                }
+            }
+        }
+
+        let mut reachable = vec![false; size];
+        for x in &productions.axiom {
+            reachable[*x] = true;
+            for (to, is_reached) in reach[*x].iter().enumerate() {
+                reachable[to] |= is_reached;
+            }
+        }
+
+        let components = components(&reach);
+        let radii: Vec<f64> = components
+            .iter()
+            .map(|component| radius(&matrix, component, &reach))
+            .collect();
+        let eigenvalue = radii.iter().copied().fold(0.0, f64::max);
+
+        let live: Vec<usize> = (0..components.len())
+            .filter(|x| reachable[components[*x][0]])
+            .collect();
+        let rate = live.iter().map(|x| radii[*x]).fold(0.0, f64::max);
+        let growth = if rate > 1.0 {
+            Growth::Exponential(rate)
+        } else if rate == 0.0 {
+            Growth::Bounded
+        } else {
+            match chain(&components, &radii, &reach, &live) {
+                0 | 1 => Growth::Bounded,
+                length => Growth::Polynomial(length - 1),
+            }
+        };
+
+        let dead = dead(&productions.successors);
+        Ok(Analysis {
+            dead: (0..size)
+                .filter(|x| dead[*x])
+                .map(|x| productions.symbols[x])
+                .collect(),
+            unreachable: (0..size)
+                .filter(|x| !reachable[*x])
+                .map(|x| productions.symbols[x])
+                .collect(),
+            symbols: productions.symbols,
+            matrix,
+            growth,
+            eigenvalue,
+        })
+    }
+}
+
+/// Group symbols into strongly connected components.
+fn components(reach: &[Vec<bool>]) -> Vec<Vec<usize>> {
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for (x, row) in reach.iter().enumerate() {
+        match components
+            .iter_mut()
+            .find(|c| reach[c[0]][x] && row[c[0]])
+        {
+            Some(component) => component.push(x),
+            None => components.push(vec![x]),
+        }
+    }
+    components
+}
+
+/// The spectral radius of the production matrix restricted to a component.
+///
+/// A component without a cycle has radius 0 and one where every symbol produces exactly
+/// one symbol of the component has radius 1, anything else has a radius above 1 found by
+/// power iteration. The iteration runs on the matrix plus the identity, which shares its
+/// eigenvectors but is aperiodic so the iteration converges.
+fn radius(matrix: &[Vec<u128>], component: &[usize], reach: &[Vec<bool>]) -> f64 {
+    if !reach[component[0]][component[0]] {
+        return 0.0;
+    }
+    let produced = |i: &usize| component.iter().map(|j| matrix[*i][*j]).sum::<u128>();
+    if component.iter().all(|i| produced(i) == 1) {
+        return 1.0;
+    }
+
+    let mut vector = vec![1.0; component.len()];
+    let mut radius = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<f64> = component
+            .iter()
+            .zip(&vector)
+            .map(|(i, x)| {
+                x + component
+                    .iter()
+                    .zip(&vector)
+                    .map(|(j, y)| matrix[*i][*j] as f64 * y)
+                    .sum::<f64>()
+            })
+            .collect();
+        let norm = next.iter().copied().fold(0.0, f64::max);
+        vector = next.into_iter().map(|x| x / norm).collect();
+        if (norm - 1.0 - radius).abs() < 1e-12 {
+            return norm - 1.0;
+        }
+        radius = norm - 1.0;
+    }
+    radius
+}
+
+/// The largest number of components with radius 1 on any path starting from a live
+/// component.
+fn chain(components: &[Vec<usize>], radii: &[f64], reach: &[Vec<bool>], live: &[usize]) -> usize {
+    fn visit(
+        x: usize,
+        components: &[Vec<usize>],
+        radii: &[f64],
+        reach: &[Vec<bool>],
+        longest: &mut Vec<Option<usize>>,
+    ) -> usize {
+        if let Some(length) = longest[x] {
+            return length;
+        }
+        let below = (0..components.len())
+            .filter(|y| *y != x && reach[components[x][0]][components[*y][0]])
+            .map(|y| visit(y, components, radii, reach, longest))
+            .max()
+            .unwrap_or(0);
+        let length = below + usize::from(radii[x] == 1.0);
+        longest[x] = Some(length);
+        length
+    }
+
+    let mut longest = vec![None; components.len()];
+    live.iter()
+        .map(|x| visit(*x, components, radii, reach, &mut longest))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Flag the symbols whose expansion eventually vanishes.
+fn dead(successors: &[Vec<usize>]) -> Vec<bool> {
+    let mut dead = vec![false; successors.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (x, successor) in successors.iter().enumerate() {
+            if !dead[x] && successor.iter().all(|y| dead[*y]) {
+                dead[x] = true;
+                changed = true;
+            }
+        }
+    }
+    dead
+}
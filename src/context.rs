@@ -1,11 +1,116 @@
-use crate::{Alphabet, Module};
+use crate::{Alphabet, Module, Role, Roles};
 
 /// The context given to each Grammar token when evaluating rules.
 pub struct Context<'a, A: Alphabet> {
 
-    /// The grammer token that came right before this one.
-    pub previous: Option<&'a Module<A>>,
+    /// Every grammar token that came before this one, the closest being last.
+    pub previous: &'a [Module<A>],
     
-    /// The grammar token that comes right after this one.
-    pub next: Option<&'a Module<A>>,
+    /// Every grammar token that comes after this one, the closest being first.
+    pub next: &'a [Module<A>],
+
+    /// The role of each grammar token, used to follow branches.
+    pub roles: Roles<A>,
+
+    /// Grammar tokens skipped over when matching contexts.
+    pub ignore: &'a [A],
+}
+
+impl<'a, A: Alphabet> Context<'a, A> {
+
+    /// Match the given left context returning the matched modules.
+    ///
+    /// Ignored tokens are skipped and complete branches are stepped over, so the
+    /// left context of a module is found on the path back to the root.
+    pub fn left(&self, pattern: &[Module<A>]) -> Option<Vec<&'a Module<A>>> {
+        let mut found = Vec::with_capacity(pattern.len());
+        let mut wanted = pattern.iter().rev().peekable();
+        let mut pos = self.previous.len();
+
+        while let Some(want) = wanted.peek() {
+            pos = pos.checked_sub(1)?;
+            let module = &self.previous[pos];
+            match (self.roles)(&module.token) {
+                Role::Push => {}
+                Role::Pop => {
+                    let mut depth = 1;
+                    while depth > 0 {
+                        pos = pos.checked_sub(1)?;
+                        match (self.roles)(&self.previous[pos].token) {
+                            Role::Push => depth -= 1,
+                            Role::Pop => depth += 1,
+                            _ => {}
+                        }
+                    }
+                }
+                _ if self.ignore.contains(&module.token) => {}
+                _ if module.token == want.token => {
+                    found.push(module);
+                    wanted.next();
+                }
+                _ => return None,
+            }
+        }
+
+        found.reverse();
+        Some(found)
+    }
+
+    /// Match the given right context returning the matched modules.
+    ///
+    /// Ignored tokens are skipped and the context may continue into any of the
+    /// following branches or past them.
+    pub fn right(&self, pattern: &[Module<A>]) -> Option<Vec<&'a Module<A>>> {
+        let mut found = Vec::with_capacity(pattern.len());
+        self.match_right(0, pattern, &mut found).then_some(found)
+    }
+
+    fn match_right(
+        &self,
+        mut pos: usize,
+        mut pattern: &[Module<A>],
+        found: &mut Vec<&'a Module<A>>,
+    ) -> bool {
+        while let Some(want) = pattern.first() {
+            let Some(module) = self.next.get(pos) else {
+                return false;
+            };
+            match (self.roles)(&module.token) {
+                Role::Push => {
+                    let mark = found.len();
+                    if self.match_right(pos + 1, pattern, found) {
+                        return true;
+                    }
+                    found.truncate(mark);
+                    pos = self.skip_branch(pos);
+                }
+                Role::Pop => return false,
+                _ if self.ignore.contains(&module.token) => pos += 1,
+                _ if module.token == want.token => {
+                    found.push(module);
+                    pattern = &pattern[1..];
+                    pos += 1;
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// The position just after the branch started at `pos`.
+    fn skip_branch(&self, mut pos: usize) -> usize {
+        let mut depth = 0;
+        while let Some(module) = self.next.get(pos) {
+            match (self.roles)(&module.token) {
+                Role::Push => depth += 1,
+                Role::Pop => depth -= 1,
+                _ => {}
+            }
+            pos += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        pos
+    }
 }
@@ -0,0 +1,14 @@
+use crate::{Alphabet, Parameters, State};
+
+/// The environment of an open LSystem, answering query modules between generations.
+///
+/// Query modules are grammar tokens with `Role::Query`, after each derivation step
+/// (and for the axiom) the environment is asked for their new parameters.
+pub trait Environment<A: Alphabet> {
+
+    /// Respond to the query module at `index` in the state returning its new parameters.
+    ///
+    /// Query modules are visited in order so an environment can interpret the state
+    /// incrementally. Returning `None` leaves the module unchanged.
+    fn query(&mut self, state: &State<A>, index: usize) -> Option<Parameters>;
+}
@@ -0,0 +1,248 @@
+use std::{error, fmt};
+
+use crate::{Conditional, Function, Operator, Value};
+
+/// The reason an expression or condition failed to evaluate.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EvalErrorKind {
+    /// An arithmetic operator was applied to a non numeric value.
+    NonNumeric(Operator, Value),
+    /// A function was called with a non numeric argument.
+    NonNumericArgument(Function, Value),
+    /// A function was called with the wrong number of arguments.
+    Arity(Function, usize),
+    /// Values were ordered when one of them is not a number.
+    NonNumericComparison(Conditional, Value),
+    /// A value used as a condition is not a number.
+    NonNumericCondition(Value),
+    /// Decomposition or homomorphism rules still matched after the given number of passes.
+    MaxDepth(usize),
+    /// The schedule picked a rule table that doesn't exist.
+    MissingTable(String),
+}
+
+impl fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalErrorKind::NonNumeric(op, val) => {
+                write!(f, "attempted to apply `{op}` to non numeric value `{val}`")
+            }
+            EvalErrorKind::NonNumericArgument(func, val) => {
+                write!(f, "attempted to call `{func}` with non numeric argument `{val}`")
+            }
+            EvalErrorKind::Arity(func, given) => write!(
+                f,
+                "`{func}` takes {} arguments but {given} were given",
+                func.arity()
+            ),
+            EvalErrorKind::NonNumericComparison(cond, val) => {
+                write!(f, "attempted to compare non numeric value `{val}` with `{cond}`")
+            }
+            EvalErrorKind::NonNumericCondition(val) => {
+                write!(f, "attempted to use non numeric value `{val}` as a condition")
+            }
+            EvalErrorKind::MaxDepth(depth) => {
+                write!(f, "rules were still rewriting after {depth} passes")
+            }
+            EvalErrorKind::MissingTable(name) => write!(f, "no rule table named `{name}`"),
+        }
+    }
+}
+
+/// An error raised while evaluating parameters or conditions during sampling.
+///
+/// Each layer of the derivation fills in what it knows about where the error happened.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EvalError {
+    pub kind: EvalErrorKind,
+
+    /// The parameter expression or condition that failed to evaluate.
+    pub expr: Option<String>,
+
+    /// Index of the rule being applied, in declaration order within its rule set.
+    pub rule: Option<usize>,
+
+    /// Index of the module being rewritten.
+    pub module: Option<usize>,
+
+    /// The generation being produced.
+    pub generation: Option<usize>,
+}
+
+impl EvalError {
+
+    /// Create a new error with no location information.
+    pub fn new(kind: EvalErrorKind) -> Self {
+        EvalError {
+            kind,
+            expr: None,
+            rule: None,
+            module: None,
+            generation: None,
+        }
+    }
+
+    pub(crate) fn expr<E: fmt::Display>(mut self, expr: &E) -> Self {
+        self.expr.get_or_insert_with(|| expr.to_string());
+        self
+    }
+
+    pub(crate) fn rule(mut self, rule: usize) -> Self {
+        self.rule.get_or_insert(rule);
+        self
+    }
+
+    pub(crate) fn module(mut self, module: usize) -> Self {
+        self.module.get_or_insert(module);
+        self
+    }
+
+    pub(crate) fn generation(mut self, generation: usize) -> Self {
+        self.generation.get_or_insert(generation);
+        self
+    }
+}
+
+impl From<EvalErrorKind> for EvalError {
+    fn from(kind: EvalErrorKind) -> Self {
+        EvalError::new(kind)
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(expr) = &self.expr {
+            write!(f, " in `{expr}`")?;
+        }
+        if let Some(rule) = self.rule {
+            write!(f, " of rule {rule}")?;
+        }
+        if let Some(module) = self.module {
+            write!(f, " rewriting module {module}")?;
+        }
+        if let Some(generation) = self.generation {
+            write!(f, " in generation {generation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for EvalError {}
+
+/// The limit that stopped sampling.
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+pub enum Limit {
+    Modules,
+    Duration,
+    Cancelled,
+}
+
+/// Sampling was stopped because a `SampleLimits` limit was exceeded.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+
+    /// The generation being produced when sampling stopped.
+    pub generation: usize,
+
+    /// The number of modules reached when sampling stopped.
+    pub modules: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::Modules => write!(f, "module limit exceeded")?,
+            Limit::Duration => write!(f, "time limit exceeded")?,
+            Limit::Cancelled => write!(f, "sampling cancelled")?,
+        }
+        write!(
+            f,
+            " in generation {} after reaching {} modules",
+            self.generation, self.modules
+        )
+    }
+}
+
+impl error::Error for LimitExceeded {}
+
+/// An error raised by `LSystem::sample_with`, `LSystem::step_with` or `LSystem::slice`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SampleError {
+    Eval(EvalError),
+    Limit(LimitExceeded),
+    Unsupported(Unsupported),
+}
+
+impl From<EvalError> for SampleError {
+    fn from(err: EvalError) -> Self {
+        SampleError::Eval(err)
+    }
+}
+
+impl From<Unsupported> for SampleError {
+    fn from(err: Unsupported) -> Self {
+        SampleError::Unsupported(err)
+    }
+}
+
+impl fmt::Display for SampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleError::Eval(err) => write!(f, "{err}"),
+            SampleError::Limit(err) => write!(f, "{err}"),
+            SampleError::Unsupported(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl error::Error for SampleError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SampleError::Eval(err) => Some(err),
+            SampleError::Limit(err) => Some(err),
+            SampleError::Unsupported(err) => Some(err),
+        }
+    }
+}
+
+/// A feature of an LSystem that stops it being expanded or analysed without sampling every
+/// generation.
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+pub enum Unsupported {
+    /// A rule has a left or right context.
+    Context,
+    /// A rule has a condition.
+    Condition,
+    /// A token has several rules, a rule doesn't always apply or a successor calls a random
+    /// function.
+    Stochastic,
+    /// The rule table is picked by a callback on the current state.
+    Callback,
+    /// Rule tables are switched between generations.
+    Tables,
+    /// Decomposition rules are applied to every generation.
+    Decomposition,
+    /// A cut module prunes the rest of its branch.
+    Cut,
+    /// A count is too large to fit in a `u128`.
+    Overflow,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsupported::Context => write!(f, "rules with a context are not supported"),
+            Unsupported::Condition => write!(f, "rules with a condition are not supported"),
+            Unsupported::Stochastic => write!(f, "stochastic rules are not supported"),
+            Unsupported::Callback => write!(f, "rule table callbacks are not supported"),
+            Unsupported::Tables => write!(f, "rule tables are not supported"),
+            Unsupported::Decomposition => write!(f, "decomposition rules are not supported"),
+            Unsupported::Cut => write!(f, "cut modules are not supported"),
+            Unsupported::Overflow => write!(f, "counts larger than a u128 are not supported"),
+        }
+    }
+}
+
+impl error::Error for Unsupported {}
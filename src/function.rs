@@ -0,0 +1,121 @@
+use std::{f32::consts::TAU, fmt};
+
+use rand::Rng;
+
+/// Mathematical function that can be called on parameters.
+///
+/// Trigonometric functions work in radians and `log` is the natural logarithm. `rand`,
+/// `uniform` and `normal` draw from the rng driving the derivation, so they are reproducible
+/// under a seed.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sqrt,
+    Abs,
+    Floor,
+    Ceil,
+    Min,
+    Max,
+    Clamp,
+    Exp,
+    Log,
+    Rand,
+    Uniform,
+    Normal,
+}
+
+impl Function {
+
+    /// Find the function with the given name.
+    pub fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "clamp" => Function::Clamp,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "rand" => Function::Rand,
+            "uniform" => Function::Uniform,
+            "normal" => Function::Normal,
+            _ => return None,
+        })
+    }
+
+    /// The name this function is called by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Sqrt => "sqrt",
+            Function::Abs => "abs",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Clamp => "clamp",
+            Function::Exp => "exp",
+            Function::Log => "log",
+            Function::Rand => "rand",
+            Function::Uniform => "uniform",
+            Function::Normal => "normal",
+        }
+    }
+
+    /// The number of arguments this function takes.
+    pub fn arity(&self) -> usize {
+        match self {
+            Function::Rand => 0,
+            Function::Min | Function::Max | Function::Uniform | Function::Normal => 2,
+            Function::Clamp => 3,
+            _ => 1,
+        }
+    }
+
+    /// Whether this function draws from the rng.
+    pub fn is_random(&self) -> bool {
+        matches!(self, Function::Rand | Function::Uniform | Function::Normal)
+    }
+
+    /// Call this function drawing any randomness from the given rng, the number of arguments
+    /// must match its arity.
+    pub(crate) fn call<R: Rng + ?Sized>(&self, args: &[f32], rng: &mut R) -> f32 {
+        match (self, args) {
+            (Function::Sin, [x]) => x.sin(),
+            (Function::Cos, [x]) => x.cos(),
+            (Function::Tan, [x]) => x.tan(),
+            (Function::Sqrt, [x]) => x.sqrt(),
+            (Function::Abs, [x]) => x.abs(),
+            (Function::Floor, [x]) => x.floor(),
+            (Function::Ceil, [x]) => x.ceil(),
+            (Function::Min, [a, b]) => a.min(*b),
+            (Function::Max, [a, b]) => a.max(*b),
+            (Function::Clamp, [x, min, max]) => x.max(*min).min(*max),
+            (Function::Exp, [x]) => x.exp(),
+            (Function::Log, [x]) => x.ln(),
+            (Function::Rand, []) => rng.gen(),
+            (Function::Uniform, [min, max]) => min + (max - min) * rng.gen::<f32>(),
+            (Function::Normal, [mean, deviation]) => {
+                // Box-Muller transform, `1 - gen` keeps the logarithm finite.
+                let radius = (-2.0 * (1.0 - rng.gen::<f32>()).ln()).sqrt();
+                mean + deviation * radius * (TAU * rng.gen::<f32>()).cos()
+            }
+            _ => unreachable!("`{self}` called with {} arguments", args.len()),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -1,14 +1,35 @@
 use crate::LSystemBuilder;
 
-use super::{parser::{parse_rule, parse_state}, Token};
+use super::{parser::{parse_rule, parse_state, parse_token}, Token};
 
 impl LSystemBuilder<Token> {
+
+    /// Create a new LSystemBuilder from a starting axiom in the grammar syntax, with rules
+    /// indexed by their predecessor token.
     pub fn new_str(axiom: &str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&str>>> {
         let (_, state) = parse_state(axiom)?;
-        Ok(LSystemBuilder::new(state))
+        Ok(LSystemBuilder::new(state).roles(Token::role).indexed())
     }
 
     pub fn rule_str(self, rule: &str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&str>>> {
         Ok(self.rule(parse_rule(rule)?.1))
     }
-}
\ No newline at end of file
+
+    pub fn decomposition_str(self, rule: &str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&str>>> {
+        Ok(self.decomposition(parse_rule(rule)?.1))
+    }
+
+    pub fn homomorphism_str(self, rule: &str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&str>>> {
+        Ok(self.homomorphism(parse_rule(rule)?.1))
+    }
+
+    pub fn table_rule_str<'a>(self, table: &str, rule: &'a str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&'a str>>> {
+        let (_, rule) = parse_rule(rule)?;
+        Ok(self.table_rule(table, rule))
+    }
+
+    pub fn ignore_str(self, tokens: &str) -> Result<LSystemBuilder<Token>, nom::Err<nom::error::Error<&str>>> {
+        let (_, tokens) = nom::multi::many1(parse_token)(tokens)?;
+        Ok(self.ignore(tokens))
+    }
+}
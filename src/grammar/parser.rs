@@ -1,70 +1,172 @@
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::{alpha1, space0}, combinator::map, error::Error, number::complete::double, IResult
+    branch::alt, bytes::complete::tag, character::complete::{alpha1, satisfy, space0}, combinator::{map, not}, number::complete::double, sequence::{preceded, terminated}, IResult
 };
 
-use crate::{Condition, Conditional, ConditionalValue, Module, Operator, Parameters, Rule, State, Value};
+use crate::{value::NEG_PRECEDENCE, Condition, Function, Conditional, Module, Operator, Parameters, Rule, State, Value};
 
 use super::Token;
 
 pub fn parse_operator(input: &str) -> IResult<&str, Operator> {
     alt((
         map(tag("+"), |_| Operator::Add),
-        map(tag("-"), |_| Operator::Sub),
+        map(terminated(tag("-"), not(tag(">"))), |_| Operator::Sub),
         map(tag("*"), |_| Operator::Mul),
         map(tag("/"), |_| Operator::Div),
+        map(tag("%"), |_| Operator::Rem),
         map(tag("^"), |_| Operator::Exponent)
     ))(input)
 }
 
 pub fn parse_conditional(input: &str) -> IResult<&str, Conditional> {
     alt((
-        map(tag("="), |_| Conditional::EqualTo),
+        map(tag(">="), |_| Conditional::GreaterOrEqual),
+        map(tag("<="), |_| Conditional::LessOrEqual),
+        map(tag("=="), |_| Conditional::EqualTo),
+        map(tag("!="), |_| Conditional::NotEqual),
         map(tag(">"), |_| Conditional::GreaterThan),
         map(tag("<"), |_| Conditional::LessThan),
-        map(tag("&"), |_| Conditional::And),
-        map(tag("|"), |_| Conditional::Or),
+        map(tag("="), |_| Conditional::EqualTo),
+    ))(input)
+}
+
+/// Parse a variable name, a letter followed by letters, digits or underscores.
+pub fn parse_name(input: &str) -> IResult<&str, &str> {
+    nom::combinator::recognize(nom::sequence::pair(
+        alpha1,
+        nom::multi::many0(alt((nom::character::complete::alphanumeric1, tag("_")))),
     ))(input)
 }
 
 pub fn parse_lit_value(input: &str) -> IResult<&str, Value> {
     alt((
+        map(parse_name, |s: &str| Value::Var(s.to_string())),
         map(double, |s| Value::Num(s as f32)),
-        map(alpha1, |s: &str| Value::Var(s.chars().next().unwrap())),
     ))(input)
 }
 
 pub fn parse_value(input: &str) -> IResult<&str, Value> {
-    alt((parse_expr, parse_lit_value))(input)
+    parse_expr(input)
 }
 
+/// Parse an expression of literals, parentheses, unary minus and binary operators.
 pub fn parse_expr(input: &str) -> IResult<&str, Value> {
-    let (input, _) = space0(input)?;
-    let (input, a) = parse_lit_value(input)?;
-    let (input, _) = space0(input)?;
-    let (input, cond) = parse_operator(input)?;
-    let (input, _) = space0(input)?;
-    let (input, b) = parse_lit_value(input)?;
-    Ok((input, Value::Expr(Box::new(a), cond, Box::new(b))))
+    parse_binary(input, 0)
+}
+
+/// Parse an operand followed by every binary operator binding at least as tightly as
+/// `precedence`, grouping them by precedence climbing.
+fn parse_binary(input: &str, precedence: u8) -> IResult<&str, Value> {
+    let (mut input, mut lhs) = parse_unary(input)?;
+    while let Ok((rest, op)) = preceded(space0, parse_operator)(input) {
+        if op.precedence() < precedence {
+            break;
+        }
+        let next = if op.is_right_associative() { op.precedence() } else { op.precedence() + 1 };
+        let (rest, rhs) = parse_binary(rest, next)?;
+        lhs = Value::Expr(Box::new(lhs), op, Box::new(rhs));
+        input = rest;
+    }
+    Ok((input, lhs))
 }
 
-pub fn parse_condition_value(input: &str) -> IResult<&str, ConditionalValue> {
+/// Parse a literal, a parenthesised expression or a negated operand.
+fn parse_unary(input: &str) -> IResult<&str, Value> {
     let (input, _) = space0(input)?;
+    if let Ok((input, _)) = tag::<_, _, nom::error::Error<&str>>("-")(input) {
+        let (input, value) = parse_binary(input, NEG_PRECEDENCE)?;
+        return Ok((input, match value {
+            Value::Num(x) => Value::Num(-x),
+            x => Value::Neg(Box::new(x)),
+        }));
+    }
     alt((
-        map(parse_value, ConditionalValue::Value),
-        map(parse_condition, |expr| ConditionalValue::Condition(Box::new(expr))),
+        nom::sequence::delimited(open_paran, parse_expr, close_paran),
+        parse_call,
+        parse_lit_value,
     ))(input)
 }
 
+/// Parse a call to a built in function like `sin(a)` or `max(a, b)`.
+pub fn parse_call(input: &str) -> IResult<&str, Value> {
+    let (input, _) = space0(input)?;
+    let call = nom::sequence::pair(
+        nom::combinator::map_opt(parse_name, Function::from_name),
+        nom::sequence::delimited(
+            open_paran,
+            nom::multi::separated_list0(comma, parse_expr),
+            close_paran,
+        ),
+    );
+    map(
+        nom::combinator::verify(call, |(func, args): &(Function, Vec<Value>)| {
+            args.len() == func.arity()
+        }),
+        |(func, args)| Value::Call(func, args),
+    )(input)
+}
+
+/// Parse a rule condition like `: t >= 3 && (w < 0.1 || !leaf)`.
+///
+/// A bare number is left for `parse_probability`, so `: 0.5` is not a condition.
 pub fn parse_condition(input: &str) -> IResult<&str, Condition> {
     let (input, _) = space0(input)?;
     let (input, _) = tag(":")(input)?;
+    nom::combinator::verify(parse_or, |cond| !matches!(cond, Condition::Value(Value::Num(_))))(input)
+}
+
+/// Parse conditions joined by `||`, which binds loosest.
+fn parse_or(input: &str) -> IResult<&str, Condition> {
+    let (mut input, mut lhs) = parse_and(input)?;
+    while let Ok((rest, _)) = preceded(space0, or_operator)(input) {
+        let (rest, rhs) = parse_and(rest)?;
+        lhs = Condition::Or(Box::new(lhs), Box::new(rhs));
+        input = rest;
+    }
+    Ok((input, lhs))
+}
+
+/// Parse conditions joined by `&&`.
+fn parse_and(input: &str) -> IResult<&str, Condition> {
+    let (mut input, mut lhs) = parse_not(input)?;
+    while let Ok((rest, _)) = preceded(space0, and_operator)(input) {
+        let (rest, rhs) = parse_not(rest)?;
+        lhs = Condition::And(Box::new(lhs), Box::new(rhs));
+        input = rest;
+    }
+    Ok((input, lhs))
+}
+
+/// Parse a logical or, also accepting the single character form.
+fn or_operator(input: &str) -> IResult<&str, &str> {
+    alt((tag("||"), tag("|")))(input)
+}
+
+/// Parse a logical and, also accepting the single character form.
+fn and_operator(input: &str) -> IResult<&str, &str> {
+    alt((tag("&&"), tag("&")))(input)
+}
+
+/// Parse a negated condition or a single comparison, parenthesised condition or value.
+fn parse_not(input: &str) -> IResult<&str, Condition> {
     let (input, _) = space0(input)?;
+    if let Ok((input, _)) = terminated(tag::<_, _, nom::error::Error<&str>>("!"), not(tag("=")))(input) {
+        let (input, cond) = parse_not(input)?;
+        return Ok((input, Condition::Not(Box::new(cond))));
+    }
+    alt((
+        parse_comparison,
+        nom::sequence::delimited(open_paran, parse_or, close_paran),
+        map(parse_value, Condition::Value),
+    ))(input)
+}
+
+/// Parse two values compared like `a >= b`.
+fn parse_comparison(input: &str) -> IResult<&str, Condition> {
     let (input, a) = parse_value(input)?;
     let (input, _) = space0(input)?;
     let (input, cond) = parse_conditional(input)?;
-    let (input, _) = space0(input)?;
-    let (input, b) = parse_condition_value(input)?;
-    Ok((input, Condition { a: ConditionalValue::Value(a), cond, b }))
+    let (input, b) = parse_value(input)?;
+    Ok((input, Condition::Compare(a, cond, b)))
 }
 
 pub fn parse_token(input: &str) -> IResult<&str, Token> {
@@ -83,7 +185,9 @@ pub fn parse_token(input: &str) -> IResult<&str, Token> {
         map(tag("."), |_| Token::PolygonVertex),
         map(tag("\\"), |_| Token::CounterRoll),
         map(tag("/"), |_| Token::Roll),
-        map(alpha1, |s: &str| Token::External(s.chars().next().unwrap())),
+        map(tag("%"), |_| Token::Cut),
+        map(nom::sequence::preceded(tag("?"), satisfy(|c| c.is_alphabetic())), Token::Query),
+        map(satisfy(|c| c.is_alphabetic()), Token::External),
     ))(input)
 }
 
@@ -129,24 +233,21 @@ pub fn parse_state(input: &str) -> IResult<&str, State<Token>> {
     map(nom::multi::many1(parse_module), State::new)(input)
 }
 
-pub fn parse_prefix(input: &str) -> IResult<&str, Option<Token>> {
-    let res = parse_token(input);
-    let (input, _) = tag::<&str, &str, Error<&str>>("<")(input)?;
-    match res {
-        Ok((input, token)) => Ok((input, Some(token))),
-        Err(_) => Ok((input, None))
-    }
+pub fn parse_prefix(input: &str) -> IResult<&str, Vec<Module<Token>>> {
+    let (input, modules) = nom::multi::many1(parse_module)(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = tag("<")(input)?;
+    Ok((input, modules))
 }
 
-pub fn parse_suffix(input: &str) -> IResult<&str, Option<Token>> {
+pub fn parse_suffix(input: &str) -> IResult<&str, Vec<Module<Token>>> {
     let (input, _) = space0(input)?;
-    let res = parse_token(input);
-    let (input, _) = tag::<&str, &str, Error<&str>>(">")(input)?;
+    let (input, _) = tag(">")(input)?;
     let (input, _) = space0(input)?;
-    match res {
-        Ok((_, token)) => Ok((input, Some(token))),
-        Err(err) => Err(err)
-    }
+    nom::multi::many1(nom::sequence::preceded(
+        nom::combinator::not(tag("->")),
+        parse_module,
+    ))(input)
 }
 
 pub fn parse_probability(input: &str) -> IResult<&str, f32> {
@@ -161,9 +262,9 @@ pub fn parse_probability(input: &str) -> IResult<&str, f32> {
 }
 
 pub fn parse_rule(input: &str) -> IResult<&str, Rule<Token>> {
-    let (input, prefix) = parse_prefix(input).unwrap_or((input, None));
+    let (input, prefix) = parse_prefix(input).unwrap_or((input, vec![]));
     let (input, module) = parse_module(input)?;
-    let (input, suffix) = parse_suffix(input).unwrap_or((input, None));
+    let (input, suffix) = parse_suffix(input).unwrap_or((input, vec![]));
     let (input, condition) = parse_condition(input).map(|(x, y)|(x, Some(y))).unwrap_or((input, None));
     let (input, probability) = parse_probability(input).unwrap_or((input, 1.0));
     let (input, _) = space0(input)?;
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::Role;
+
 #[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
 pub enum Token {
     F,
@@ -15,7 +17,23 @@ pub enum Token {
     EndPolygon,
     StartPolygon,
     PolygonVertex,
+    Cut,
     External(char),
+    Query(char),
+}
+
+impl Token {
+
+    /// The role this token plays when resolving rule contexts.
+    pub fn role(&self) -> Role {
+        match self {
+            Token::Push => Role::Push,
+            Token::Pop => Role::Pop,
+            Token::Query(_) => Role::Query,
+            Token::Cut => Role::Cut,
+            _ => Role::Symbol,
+        }
+    }
 }
 
 impl fmt::Display for Token {
@@ -34,7 +52,9 @@ impl fmt::Display for Token {
             Token::StartPolygon => write!(f, "{{"),
             Token::PolygonVertex => write!(f, "."),
             Token::CounterRoll => write!(f, "\\"),
+            Token::Cut => write!(f, "%"),
             Token::External(x) => write!(f, "{x}"),
+            Token::Query(x) => write!(f, "?{x}"),
         }
     }
 }
\ No newline at end of file
@@ -2,22 +2,37 @@
 
 mod alphabet;
 mod context;
+mod environment;
+mod error;
+mod function;
 mod lsystem;
 mod module;
 mod operator;
+mod role;
 mod rules;
 mod value;
 
+pub mod analysis;
+
 #[cfg(feature = "grammar")]
 pub mod grammar;
 
 pub use self::alphabet::Alphabet;
 pub use self::context::Context;
-pub use self::lsystem::{Axiom, LSystem, LSystemBuilder, State};
+pub use self::environment::Environment;
+pub use self::function::Function;
+pub use self::error::{
+    EvalError, EvalErrorKind, Limit, LimitExceeded, SampleError, Unsupported,
+};
+pub use self::lsystem::{
+    Axiom, Expand, Generations, LSystem, LSystemBuilder, Provenance, SampleLimits, SampleOptions,
+    Schedule, State, TableCallback,
+};
 pub use self::module::Module;
 pub use self::operator::Operator;
-pub use self::rules::{Condition, Conditional, ConditionalValue, Rule, Rules};
+pub use self::role::{Role, Roles};
+pub use self::rules::{Condition, Conditional, Rule, RuleKey, Rules, Selection};
 pub use self::value::Value;
 
 pub type Parameters = Vec<Value>;
-pub type Variables = std::collections::HashMap<char, Value>;
+pub type Variables = std::collections::HashMap<String, Value>;
@@ -1,10 +1,29 @@
-use crate::{Alphabet, Axiom, LSystem, Rule, Rules, Value, Variables};
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    rules::hash_key, Alphabet, Axiom, LSystem, Role, Roles, Rule, RuleKey, Rules, Schedule,
+    Selection, Value, Variables,
+};
 
 /// Builder struct to create a LSystem.
+///
+/// Every module is checked against each rule in declaration order unless the rules are indexed
+/// with `LSystemBuilder::indexed` or `LSystemBuilder::index_by`, which is worth enabling for
+/// grammars with many rules. `LSystemBuilder::new_str` indexes its rules by default.
+#[derive(Debug, Clone)]
 pub struct LSystemBuilder<A: Alphabet> {
     axiom: Axiom<A>,
     rules: Rules<A>,
+    decomposition: Rules<A>,
+    homomorphism: Rules<A>,
+    tables: HashMap<String, Rules<A>>,
+    schedule: Option<Schedule<A>>,
     variables: Variables,
+    seed: Option<u64>,
+    selection: Selection,
+    key: Option<RuleKey<A>>,
+    roles: Roles<A>,
+    ignore: Vec<A>,
 }
 
 impl<A: Alphabet> LSystemBuilder<A> {
@@ -14,13 +33,53 @@ impl<A: Alphabet> LSystemBuilder<A> {
         LSystemBuilder {
             axiom: axiom.into(),
             rules: Default::default(),
+            decomposition: Default::default(),
+            homomorphism: Default::default(),
+            tables: Default::default(),
+            schedule: None,
             variables: Default::default(),
+            seed: None,
+            selection: Default::default(),
+            key: None,
+            roles: |_| Role::Symbol,
+            ignore: Default::default(),
         }
     }
 
     /// Insert a variable into the variable cache.
-    pub fn variable(mut self, key: char, val: Value) -> Self {
-        self.variables.insert(key, val);
+    pub fn variable<K: Into<String>>(mut self, key: K, val: Value) -> Self {
+        self.variables.insert(key.into(), val);
+        self
+    }
+
+    /// Seed the random number generator so every sample is reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set how competing stochastic rules are chosen between, defaults to `Selection::Weighted`.
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Index rules by the given key so each module is only checked against rules whose
+    /// predecessor shares its key.
+    pub fn index_by(mut self, key: RuleKey<A>) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Declare the role of each grammar token so contexts follow the branching structure.
+    pub fn roles(mut self, roles: Roles<A>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Skip over the given grammar tokens when matching contexts.
+    pub fn ignore<I: IntoIterator<Item = A>>(mut self, tokens: I) -> Self {
+        self.ignore.extend(tokens);
         self
     }
 
@@ -30,8 +89,58 @@ impl<A: Alphabet> LSystemBuilder<A> {
         self
     }
 
+    /// Push a decomposition rule, applied to the axiom and straight after every generation.
+    pub fn decomposition(mut self, rule: Rule<A>) -> Self {
+        self.decomposition.append(rule);
+        self
+    }
+
+    /// Push a homomorphism rule, applied only when interpreting a state.
+    pub fn homomorphism(mut self, rule: Rule<A>) -> Self {
+        self.homomorphism.append(rule);
+        self
+    }
+
+    /// Push a rule onto the named rule table, creating the table if needed.
+    pub fn table_rule(mut self, table: &str, rule: Rule<A>) -> Self {
+        self.tables.entry(table.to_string()).or_default().append(rule);
+        self
+    }
+
+    /// Set the schedule deciding which rule table rewrites each generation.
+    pub fn schedule(mut self, schedule: Schedule<A>) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
     /// Finishing building and return the created LSystem.
-    pub fn build(self) -> LSystem<A> {
-        LSystem::new(self.axiom, self.rules, self.variables)
+    pub fn build(mut self) -> LSystem<A> {
+        let rule_sets = [&mut self.rules, &mut self.decomposition, &mut self.homomorphism]
+            .into_iter()
+            .chain(self.tables.values_mut());
+        for rules in rule_sets {
+            rules.selection = self.selection;
+            if let Some(key) = self.key {
+                rules.index_by(key);
+            }
+        }
+
+        let mut lsystem = LSystem::new(self.axiom, self.rules, self.variables);
+        lsystem.decomposition = self.decomposition;
+        lsystem.homomorphism = self.homomorphism;
+        lsystem.tables = self.tables;
+        lsystem.schedule = self.schedule;
+        lsystem.seed = self.seed;
+        lsystem.roles = self.roles;
+        lsystem.ignore = self.ignore;
+        lsystem
+    }
+}
+
+impl<A: Alphabet + Hash> LSystemBuilder<A> {
+
+    /// Index rules by the hash of their predecessor token.
+    pub fn indexed(self) -> Self {
+        self.index_by(hash_key::<A>)
     }
 }
@@ -0,0 +1,285 @@
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use rand::rngs::StdRng;
+
+use crate::{
+    Alphabet, Context, EvalError, LSystem, Module, SampleError, Selection, State, Unsupported,
+    Value, Variables,
+};
+
+/// The productions of a deterministic context free LSystem over its numbered symbols.
+pub(crate) struct Productions<A: Alphabet> {
+    /// Every token in the axiom and rules, in order of first appearance.
+    pub symbols: Vec<A>,
+
+    /// The axiom as symbol numbers.
+    pub axiom: Vec<usize>,
+
+    /// The successor of each symbol, symbols without a rule are their own successor.
+    pub successors: Vec<Vec<usize>>,
+}
+
+impl<A: Alphabet> Productions<A> {
+
+    /// Collect the productions of an LSystem with exactly one unconditional rule per token,
+    /// whose successors don't call random functions.
+    ///
+    /// Rules that can never apply are ignored. Under `Selection::Weighted` a lone rule always
+    /// applies whatever its probability. Under `Selection::Sequential` the first rule for a
+    /// token must have a probability of at least 1, and shadows any rules after it.
+    pub fn new(lsystem: &LSystem<A>) -> Result<Self, Unsupported> {
+        lsystem.context_free()?;
+        if lsystem.schedule.is_some() {
+            return Err(Unsupported::Tables);
+        }
+
+        let mut symbols = Vec::new();
+        let mut number = |token: &A| match symbols.iter().position(|x| x == token) {
+            Some(x) => x,
+            None => {
+                symbols.push(*token);
+                symbols.len() - 1
+            }
+        };
+
+        let axiom = lsystem.axiom.inner.iter().map(|x| number(&x.token)).collect();
+        let selection = lsystem.rules.selection;
+        let mut rules: Vec<(usize, Vec<usize>)> = Vec::new();
+        for rule in lsystem.rules.iter() {
+            let predecessor = number(&rule.pattern.token);
+            let shadowed = rules.iter().any(|x| x.0 == predecessor);
+            if rule.probability <= 0.0 || (shadowed && selection == Selection::Sequential) {
+                continue;
+            }
+            if rule.condition.is_some() {
+                return Err(Unsupported::Condition);
+            }
+            let stochastic = match selection {
+                Selection::Weighted => shadowed,
+                Selection::Sequential => rule.probability < 1.0,
+            };
+            if stochastic || rule.tokens.iter().flat_map(|x| &x.params).any(Value::is_random) {
+                return Err(Unsupported::Stochastic);
+            }
+            rules.push((predecessor, rule.tokens.iter().map(|x| number(&x.token)).collect()));
+        }
+
+        let mut successors: Vec<Vec<usize>> = (0..symbols.len()).map(|x| vec![x]).collect();
+        for (predecessor, successor) in rules {
+            successors[predecessor] = successor;
+        }
+
+        Ok(Productions {
+            symbols,
+            axiom,
+            successors,
+        })
+    }
+
+    /// The number of the given token, if it appears in the axiom or rules.
+    pub fn symbol(&self, token: &A) -> Option<usize> {
+        self.symbols.iter().position(|x| x == token)
+    }
+
+    /// The length each symbol expands to after every generation up to the given one,
+    /// saturating on overflow.
+    pub fn lengths(&self, generation: usize) -> Vec<Vec<u128>> {
+        let mut lengths = vec![vec![1; self.symbols.len()]];
+        for depth in 0..generation {
+            let row = self
+                .successors
+                .iter()
+                .map(|successor| {
+                    successor
+                        .iter()
+                        .fold(0u128, |acc, x| acc.saturating_add(lengths[depth][*x]))
+                })
+                .collect();
+            lengths.push(row);
+        }
+        lengths
+    }
+
+    /// The growth matrix, where entry `[i][j]` counts the symbol `j` in the successor of `i`.
+    pub fn matrix(&self) -> Vec<Vec<u128>> {
+        self.successors
+            .iter()
+            .map(|successor| {
+                let mut row = vec![0; self.symbols.len()];
+                for x in successor {
+                    row[*x] += 1;
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// The number of each symbol in the axiom.
+    pub fn parikh(&self) -> Vec<u128> {
+        let mut counts = vec![0; self.symbols.len()];
+        for x in &self.axiom {
+            counts[*x] += 1;
+        }
+        counts
+    }
+}
+
+/// Multiply a row vector by a square matrix, entries are `None` once they overflow.
+fn mul_vector(vector: &[Option<u128>], matrix: &[Vec<Option<u128>>]) -> Vec<Option<u128>> {
+    (0..vector.len())
+        .map(|j| {
+            vector
+                .iter()
+                .zip(matrix)
+                .try_fold(0u128, |acc, (x, row)| match (*x, row[j]) {
+                    (Some(0), _) | (_, Some(0)) => Some(acc),
+                    (Some(x), Some(y)) => acc.checked_add(x.checked_mul(y)?),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+/// Multiply two square matrices, entries are `None` once they overflow.
+fn mul_matrix(a: &[Vec<Option<u128>>], b: &[Vec<Option<u128>>]) -> Vec<Vec<Option<u128>>> {
+    a.iter().map(|row| mul_vector(row, b)).collect()
+}
+
+impl<A: Alphabet + Hash + Eq> LSystem<A> {
+
+    /// Count each token in the given generation without sampling it.
+    ///
+    /// The counts are the Parikh vector of the axiom times the growth matrix raised to the
+    /// generation, found by repeated squaring. Tokens that don't appear are left out.
+    ///
+    /// Only deterministic context free LSystems are supported, with at most one rule per
+    /// token and no conditions. Returns `Unsupported::Overflow` if a count doesn't fit in a
+    /// `u128`.
+    pub fn symbol_counts(&self, generation: usize) -> Result<HashMap<A, u128>, Unsupported> {
+        let productions = Productions::new(self)?;
+        let mut counts: Vec<_> = productions.parikh().into_iter().map(Some).collect();
+        let mut matrix: Vec<Vec<_>> = productions
+            .matrix()
+            .into_iter()
+            .map(|row| row.into_iter().map(Some).collect())
+            .collect();
+
+        let mut generation = generation;
+        while generation > 0 {
+            if generation & 1 == 1 {
+                counts = mul_vector(&counts, &matrix);
+            }
+            generation >>= 1;
+            if generation > 0 {
+                matrix = mul_matrix(&matrix, &matrix);
+            }
+        }
+
+        let counts = counts.into_iter().collect::<Option<Vec<_>>>();
+        Ok(productions
+            .symbols
+            .into_iter()
+            .zip(counts.ok_or(Unsupported::Overflow)?)
+            .filter(|x| x.1 > 0)
+            .collect())
+    }
+}
+
+impl<A: Alphabet> LSystem<A> {
+
+    /// Find the module at the given index of a generation without sampling it.
+    ///
+    /// Returns `None` if the generation has fewer modules, see `LSystem::slice`.
+    pub fn module_at(
+        &self,
+        generation: usize,
+        index: usize,
+    ) -> Result<Option<Module<A>>, SampleError> {
+        Ok(self.slice(generation, index..index.saturating_add(1))?.inner.pop())
+    }
+
+    /// Build the modules within the given range of a generation without sampling it.
+    ///
+    /// The length every token expands to is precomputed, so only the modules on the path
+    /// to the range are rewritten. Only deterministic context free LSystems are supported,
+    /// with at most one rule per token, no conditions and no random functions. Returns an
+    /// error if the LSystem is unsupported or a parameter fails to evaluate.
+    pub fn slice(&self, generation: usize, range: Range<usize>) -> Result<State<A>, SampleError> {
+        let productions = Productions::new(self)?;
+        let mut window = Window {
+            lsystem: self,
+            lengths: productions.lengths(generation),
+            productions,
+            variables: (1..=generation).map(|x| self.variables(x)).collect(),
+            generation,
+            range: range.start as u128..range.end as u128,
+            rng: self.rng(),
+            out: Vec::new(),
+        };
+
+        let mut offset = 0;
+        for module in self.axiom.inner.iter() {
+            if offset >= window.range.end {
+                break;
+            }
+            offset = window.visit(module, 0, offset)?;
+        }
+
+        Ok(State { inner: window.out })
+    }
+}
+
+/// Walks the derivation tree of a deterministic LSystem collecting the modules in a range.
+struct Window<'a, A: Alphabet> {
+    lsystem: &'a LSystem<A>,
+    productions: Productions<A>,
+    lengths: Vec<Vec<u128>>,
+    variables: Vec<Variables>,
+    generation: usize,
+    range: Range<u128>,
+    rng: StdRng,
+    out: Vec<Module<A>>,
+}
+
+impl<A: Alphabet> Window<'_, A> {
+
+    /// Visit a module of the given generation starting at `offset` in the final state,
+    /// returning the offset just past its expansion.
+    fn visit(&mut self, module: &Module<A>, depth: usize, offset: u128) -> Result<u128, EvalError> {
+        let symbol = self
+            .productions
+            .symbol(&module.token)
+            .expect("every token is numbered");
+        let end = offset.saturating_add(self.lengths[self.generation - depth][symbol]);
+        if end <= self.range.start || offset >= self.range.end {
+            return Ok(end);
+        }
+        if depth == self.generation {
+            self.out.push(module.clone());
+            return Ok(end);
+        }
+
+        let context = Context {
+            previous: &[],
+            next: &[],
+            roles: self.lsystem.roles,
+            ignore: &self.lsystem.ignore,
+        };
+        let successor = self
+            .lsystem
+            .rules
+            .process(module, context, &self.variables[depth], &mut self.rng)
+            .map_err(|err| err.generation(depth + 1))?
+            .map(|x| x.inner)
+            .unwrap_or_else(|| vec![module.clone()]);
+
+        let mut offset = offset;
+        for child in &successor {
+            if offset >= self.range.end {
+                break;
+            }
+            offset = self.visit(child, depth + 1, offset)?;
+        }
+        Ok(end)
+    }
+}
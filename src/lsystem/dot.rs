@@ -0,0 +1,79 @@
+use std::fmt;
+
+use crate::{Alphabet, EvalError, LSystem, Module, Provenance, State};
+
+impl<A: Alphabet + fmt::Display> LSystem<A> {
+
+    /// Sample this LSystem and render its derivation tree as a Graphviz DOT graph, returning
+    /// an error if a parameter or condition fails to evaluate.
+    ///
+    /// Every module of every generation is a node, with edges from each module to its
+    /// successors labelled with the index and text of the rule that rewrote it. Modules
+    /// carried over unchanged are joined by a dashed edge.
+    pub fn derivation_dot(&self, generation: usize) -> Result<String, EvalError> {
+        let mut provenance = vec![Provenance::default(); self.axiom.inner.len()];
+        let mut state = self.start(&mut self.generation_rng(0), Some(&mut provenance), |_, _| {
+            Ok::<_, EvalError>(())
+        })?;
+
+        let mut out = String::from("digraph derivation {\n    node [shape=plaintext];\n");
+        nodes(&mut out, 0, &state);
+        for generation in 1..=generation {
+            let rules = self.table(&state, generation)?;
+            let mut rng = self.generation_rng(generation);
+            state = self.derive(&state, generation, &mut rng, Some(&mut provenance), |_, _| {
+                Ok::<_, EvalError>(())
+            })?;
+            nodes(&mut out, generation, &state);
+
+            for (index, origin) in provenance.iter().enumerate() {
+                let Some(parent) = origin.parent else {
+                    continue;
+                };
+                out.push_str(&format!("    g{}_{parent} -> g{generation}_{index}", generation - 1));
+                let rule = origin
+                    .rule
+                    .filter(|_| origin.generation == generation)
+                    .and_then(|x| Some((x, rules.iter().nth(x)?)));
+                match rule {
+                    Some((index, rule)) => out.push_str(&format!(
+                        " [label=\"{index}: {}\"];\n",
+                        escape(&rule.to_string())
+                    )),
+                    None => out.push_str(" [style=dashed];\n"),
+                }
+            }
+        }
+        out.push_str("}\n");
+        Ok(out)
+    }
+}
+
+/// Write a node for every module in the state.
+fn nodes<A: Alphabet + fmt::Display>(out: &mut String, generation: usize, state: &State<A>) {
+    for (index, module) in state.inner.iter().enumerate() {
+        out.push_str(&format!(
+            "    g{generation}_{index} [label=\"{}\"];\n",
+            escape(&label(module))
+        ));
+    }
+}
+
+/// Escape a label for use inside a quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The token of a module followed by its parameters.
+fn label<A: Alphabet + fmt::Display>(module: &Module<A>) -> String {
+    if module.params.is_empty() {
+        return module.token.to_string();
+    }
+    let params = module
+        .params
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}({params})", module.token)
+}
@@ -0,0 +1,74 @@
+use rand::rngs::StdRng;
+
+use crate::{Alphabet, Context, EvalError, LSystem, Module, Rules, Variables};
+
+/// Iterator streaming a generation of a context free LSystem, created with `LSystem::expand_iter`.
+///
+/// Each axiom module is expanded depth first so only one successor per generation is held
+/// in memory at a time. If a parameter or condition fails to evaluate the error is yielded
+/// and the iterator stops.
+pub struct Expand<'a, A: Alphabet> {
+    lsystem: &'a LSystem<A>,
+    generation: usize,
+    tables: Vec<Result<&'a Rules<A>, EvalError>>,
+    variables: Vec<Variables>,
+    stack: Vec<(usize, std::vec::IntoIter<Module<A>>)>,
+    rng: StdRng,
+}
+
+impl<'a, A: Alphabet> Expand<'a, A> {
+    pub(crate) fn new(lsystem: &'a LSystem<A>, generation: usize, rng: StdRng) -> Self {
+        let empty = Default::default();
+        Expand {
+            tables: (1..=generation).map(|x| lsystem.table(&empty, x)).collect(),
+            variables: (1..=generation).map(|x| lsystem.variables(x)).collect(),
+            stack: vec![(0, lsystem.axiom.inner.inner.clone().into_iter())],
+            lsystem,
+            generation,
+            rng,
+        }
+    }
+}
+
+impl<A: Alphabet> Iterator for Expand<'_, A> {
+    type Item = Result<Module<A>, EvalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        'next: loop {
+            let (depth, modules) = self.stack.last_mut()?;
+            let depth = *depth;
+            let Some(module) = modules.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            // Carry the module down until a rule rewrites it or the generation is reached.
+            for depth in depth..self.generation {
+                let context = Context {
+                    previous: &[],
+                    next: &[],
+                    roles: self.lsystem.roles,
+                    ignore: &self.lsystem.ignore,
+                };
+                let produced = self.tables[depth].clone().and_then(|rules| {
+                    rules
+                        .process(&module, context, &self.variables[depth], &mut self.rng)
+                        .map_err(|err| err.generation(depth + 1))
+                });
+                let produced = match produced {
+                    Ok(produced) => produced,
+                    Err(err) => {
+                        self.stack.clear();
+                        return Some(Err(err));
+                    }
+                };
+                if let Some(state) = produced {
+                    self.stack.push((depth + 1, state.inner.into_iter()));
+                    continue 'next;
+                }
+            }
+
+            return Some(Ok(module));
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{Alphabet, EvalError, LSystem, State};
+
+/// Iterator over the generations of an LSystem, created with `LSystem::generations`.
+///
+/// If a parameter or condition fails to evaluate the error is yielded and the iterator stops.
+pub struct Generations<'a, A: Alphabet> {
+    lsystem: &'a LSystem<A>,
+    state: Option<State<A>>,
+    generation: usize,
+}
+
+impl<'a, A: Alphabet> Generations<'a, A> {
+    pub(crate) fn new(lsystem: &'a LSystem<A>) -> Self {
+        Generations {
+            state: Some(State::from(&lsystem.axiom)),
+            generation: 0,
+            lsystem,
+        }
+    }
+
+    /// The number of the generation that will be returned next.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+}
+
+impl<A: Alphabet> Iterator for Generations<'_, A> {
+    type Item = Result<State<A>, EvalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let previous = self.state.as_ref()?;
+        let state = match self.generation {
+            0 => self
+                .lsystem
+                .start(&mut self.lsystem.generation_rng(0), None, |_, _| Ok(())),
+            generation => self.lsystem.step(previous, generation),
+        };
+        self.state = state.as_ref().ok().cloned();
+        self.generation += 1;
+        Some(state)
+    }
+}
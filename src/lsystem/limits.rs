@@ -0,0 +1,57 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{Limit, LimitExceeded, SampleError};
+
+/// How often, in rewritten modules, the clock and cancel flag are checked, a power of two.
+const CHECK_INTERVAL: usize = 1024;
+
+/// Limits that stop sampling before a runaway grammar exhausts memory or time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SampleLimits<'a> {
+    /// The maximum number of modules a generation may reach.
+    pub max_modules: Option<usize>,
+
+    /// The maximum time sampling may take.
+    pub max_duration: Option<Duration>,
+
+    /// Sampling stops once this flag is set.
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+impl SampleLimits<'_> {
+
+    /// Check the limits while producing `generation` after rewriting `index` modules into
+    /// `modules` modules.
+    pub(crate) fn check(
+        &self,
+        start: Instant,
+        generation: usize,
+        index: usize,
+        modules: usize,
+    ) -> Result<(), SampleError> {
+        let exceeded = |limit| {
+            Err(SampleError::Limit(LimitExceeded {
+                limit,
+                generation,
+                modules,
+            }))
+        };
+
+        if self.max_modules.is_some_and(|max| modules > max) {
+            return exceeded(Limit::Modules);
+        }
+        if index & (CHECK_INTERVAL - 1) != 0 {
+            return Ok(());
+        }
+        if self.max_duration.is_some_and(|max| start.elapsed() > max) {
+            return exceeded(Limit::Duration);
+        }
+        if self.cancel.is_some_and(|x| x.load(Ordering::Relaxed)) {
+            return exceeded(Limit::Cancelled);
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,55 @@
-use crate::{Alphabet, Axiom, Context, Rules, State, Variables};
+use std::{collections::HashMap, time::Instant};
+
+use rand::{rngs::StdRng, thread_rng, Rng, RngCore, SeedableRng};
+
+use crate::{
+    Alphabet, Axiom, Context, Environment, EvalError, EvalErrorKind, Expand, Generations, Module,
+    Provenance, Role, Roles, Rules, SampleError, SampleOptions, Schedule, State, Unsupported,
+    Variables,
+};
+
+/// How many times decomposition and homomorphism rules are reapplied to their own output
+/// before sampling fails.
+const MAX_DEPTH: usize = 32;
+
+/// Odd constant spreading generation numbers across the seed space.
+const GENERATION_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
 
 #[derive(Debug, Clone)]
 pub struct LSystem<A: Alphabet> {
     pub axiom: Axiom<A>,
     pub rules: Rules<A>,
+
+    /// Rules splitting modules into their components, applied to the axiom and straight after
+    /// every generation.
+    pub decomposition: Rules<A>,
+
+    /// Rules applied only when interpreting a state, their output never feeds back into
+    /// the next generation.
+    pub homomorphism: Rules<A>,
+
+    /// Named rule tables, the schedule decides which one rewrites each generation.
+    pub tables: HashMap<String, Rules<A>>,
+
+    /// Picks the rule table for each generation, when `None` the default rules are always used.
+    ///
+    /// Sampling fails if the schedule picks a table that doesn't exist.
+    pub schedule: Option<Schedule<A>>,
+
     pub variables: Variables,
+
+    /// Seed used to drive every random choice made while sampling.
+    ///
+    /// Each generation draws from its own rng derived from the seed, so stepping a sampled
+    /// state reproduces the next sample. When `None` each call to `sample` uses a fresh
+    /// thread local rng.
+    pub seed: Option<u64>,
+
+    /// The role of each grammar token, used to follow branches when matching contexts.
+    pub roles: Roles<A>,
+
+    /// Grammar tokens skipped over when matching contexts.
+    pub ignore: Vec<A>,
 }
 
 impl<A: Alphabet> LSystem<A> {
@@ -14,53 +59,454 @@ impl<A: Alphabet> LSystem<A> {
         Self {
             axiom,
             rules,
+            decomposition: Default::default(),
+            homomorphism: Default::default(),
+            tables: Default::default(),
+            schedule: None,
             variables,
+            seed: None,
+            roles: |_| Role::Symbol,
+            ignore: Default::default(),
         }
     }
 
     /// Evaluate this LSystem and return the generated LSystem state.
+    ///
+    /// If a seed has been set the same seed will always produce the same state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter or condition fails to evaluate, see `LSystem::try_sample`.
     pub fn sample(&self, generation: usize) -> State<A> {
-        let mut state = State::<A>::from(&self.axiom);
-        let mut variables = self.variables.clone();
+        self.try_sample(generation)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        if generation == 0 {
-            return state;
+    /// Evaluate this LSystem, returning an error if a parameter or condition fails to evaluate.
+    pub fn try_sample(&self, generation: usize) -> Result<State<A>, EvalError> {
+        let mut state =
+            self.start(&mut self.generation_rng(0), None, |_, _| Ok::<_, EvalError>(()))?;
+
+        for generation in 1..=generation {
+            state = self.step(&state, generation)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Evaluate this LSystem with the given options, see `SampleOptions`.
+    ///
+    /// Returns an error if a parameter or condition fails to evaluate or a limit is exceeded.
+    pub fn sample_with(
+        &self,
+        generation: usize,
+        mut options: SampleOptions<'_, A>,
+    ) -> Result<State<A>, SampleError> {
+        let start = Instant::now();
+        let limits = options.limits;
+        limits.check(start, 0, 0, self.axiom.inner.len())?;
+        if let Some(provenance) = options.provenance.as_deref_mut() {
+            *provenance = vec![Provenance::default(); self.axiom.inner.len()];
+        }
+
+        let mut state = self.with_rng(options.rng.as_deref_mut(), 0, |rng| {
+            self.start(rng, options.provenance.as_deref_mut(), |index, modules| {
+                limits.check(start, 0, index, modules)
+            })
+        })?;
+        if let Some(environment) = options.environment.as_deref_mut() {
+            self.query(&mut state, environment);
         }
 
         for generation in 1..=generation {
-            variables.insert('T', crate::Value::Num(generation as f32));
-            state.inner = state
-                .inner
-                .clone()
-                .into_iter()
-                .enumerate()
-                .map(|(index, pattern)| -> State<A> {
-                    let context = if index == 0 {
-                        Context {
-                            previous: None,
-                            next: state.inner.get(index + 1),
-                        }
-                    } else if index == state.inner.len() - 1 {
-                        Context {
-                            previous: state.inner.get(index - 1),
-                            next: None,
-                        }
-                    } else {
-                        Context {
-                            previous: state.inner.get(index - 1),
-                            next: state.inner.get(index + 1),
-                        }
-                    };
-                    self.rules
-                        .process(&pattern, context, &variables)
-                        .unwrap_or_else(|| State {
-                            inner: vec![pattern],
-                        })
-                })
-                .flat_map(|x|x.inner)
-                .collect();
+            state = self.advance(&state, generation, &mut options, start)?;
         }
 
+        Ok(state)
+    }
+
+    /// Ask the environment for the parameters of every query module in the state.
+    pub fn query<E: Environment<A> + ?Sized>(&self, state: &mut State<A>, environment: &mut E) {
+        for index in 0..state.inner.len() {
+            if (self.roles)(&state.inner[index].token) != Role::Query {
+                continue;
+            }
+            if let Some(params) = environment.query(state, index) {
+                state.inner[index].params = params;
+            }
+        }
+    }
+
+    /// Iterate over every generation of this LSystem starting with the axiom.
+    ///
+    /// Each generation is derived from the previous one, so taking the first `n`
+    /// generations does the same amount of work as `sample(n)`.
+    pub fn generations(&self) -> Generations<'_, A> {
+        Generations::new(self)
+    }
+
+    /// Stream the given generation of a context free LSystem without building any
+    /// intermediate state.
+    ///
+    /// Modules are expanded depth first, so memory grows with the generation rather than the
+    /// length of the state. Deterministic systems produce the same modules as `sample`, while
+    /// stochastic ones draw from the rng in a different order.
+    ///
+    /// Returns an error if any rule has a context, decomposition rules or cut modules are
+    /// used, or the rule table is picked by a callback. The iterator yields an error and stops
+    /// if a parameter or condition fails to evaluate.
+    pub fn expand_iter(&self, generation: usize) -> Result<Expand<'_, A>, Unsupported> {
+        self.context_free()?;
+        Ok(Expand::new(self, generation, self.rng()))
+    }
+
+    /// Advance the given state by a single generation, returning an error if a parameter or
+    /// condition fails to evaluate.
+    ///
+    /// `generation` is the number of the generation being produced and is bound to
+    /// the variable `T`. If a seed has been set the result is reproducible for the
+    /// same state and generation, and stepping `sample(n - 1)` gives `sample(n)`.
+    pub fn step(&self, state: &State<A>, generation: usize) -> Result<State<A>, EvalError> {
+        self.derive(state, generation, &mut self.generation_rng(generation), None, |_, _| Ok(()))
+    }
+
+    /// Advance the given state by a single generation with the given options, see
+    /// `SampleOptions`.
+    ///
+    /// Returns an error if a parameter or condition fails to evaluate or a limit is exceeded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provenance in the options doesn't have an entry for every module.
+    pub fn step_with(
+        &self,
+        state: &State<A>,
+        generation: usize,
+        mut options: SampleOptions<'_, A>,
+    ) -> Result<State<A>, SampleError> {
+        if let Some(provenance) = options.provenance.as_deref() {
+            assert_eq!(state.inner.len(), provenance.len(), "missing provenance for state");
+        }
+        self.advance(state, generation, &mut options, Instant::now())
+    }
+
+    /// Produce the next generation with the given options, checking the limits against the
+    /// time sampling started.
+    fn advance(
+        &self,
+        state: &State<A>,
+        generation: usize,
+        options: &mut SampleOptions<'_, A>,
+        start: Instant,
+    ) -> Result<State<A>, SampleError> {
+        let limits = options.limits;
+        let check = |index, modules| limits.check(start, generation, index, modules);
+        let mut state = self.with_rng(options.rng.as_deref_mut(), generation, |rng| {
+            self.derive(state, generation, rng, options.provenance.as_deref_mut(), check)
+        })?;
+        if let Some(environment) = options.environment.as_deref_mut() {
+            self.query(&mut state, environment);
+        }
+        Ok(state)
+    }
+
+    /// The state of generation 0, the axiom with the decomposition rules applied.
+    ///
+    /// When given the provenance of the axiom it is replaced with the provenance of the result.
+    pub(crate) fn start<R, E, F>(
+        &self,
+        rng: &mut R,
+        origins: Option<&mut Vec<Provenance>>,
+        check: F,
+    ) -> Result<State<A>, E>
+    where
+        R: Rng + ?Sized,
+        E: From<EvalError>,
+        F: FnMut(usize, usize) -> Result<(), E>,
+    {
+        self.decompose(State::from(&self.axiom), 0, rng, origins, check)
+    }
+
+    /// Produce the next generation, calling `check` with the number of modules rewritten and
+    /// produced so far after every module.
+    ///
+    /// When given the provenance of `state` it is replaced with the provenance of the result.
+    pub(crate) fn derive<R, E, F>(
+        &self,
+        state: &State<A>,
+        generation: usize,
+        rng: &mut R,
+        mut origins: Option<&mut Vec<Provenance>>,
+        mut check: F,
+    ) -> Result<State<A>, E>
+    where
+        R: Rng + ?Sized,
+        E: From<EvalError>,
+        F: FnMut(usize, usize) -> Result<(), E>,
+    {
+        let variables = self.variables(generation);
+        let rules = self.table(state, generation)?;
+
+        let mut inner = Vec::with_capacity(state.inner.len());
+        let mut derived = Vec::new();
+        for index in 0..state.inner.len() {
+            let rule = self
+                .rewrite(rules, &state.inner, index, &variables, rng, &mut inner)
+                .map_err(|err| err.generation(generation))?;
+            if let Some(origins) = origins.as_deref() {
+                let origin = match rule {
+                    Some(rule) => Provenance {
+                        rule: Some(rule),
+                        generation,
+                        parent: Some(index),
+                    },
+                    None => Provenance {
+                        parent: Some(index),
+                        ..origins[index]
+                    },
+                };
+                derived.resize(inner.len(), origin);
+            }
+            check(index + 1, inner.len())?;
+        }
+        if let Some(origins) = origins.as_deref_mut() {
+            *origins = derived;
+        }
+
+        let state = self.decompose(
+            State { inner },
+            generation,
+            rng,
+            origins.as_deref_mut(),
+            &mut check,
+        )?;
+        let state = self.cut(state, origins);
+        check(0, state.inner.len())?;
+        Ok(state)
+    }
+
+    /// Evaluate this LSystem and interpret the generated state with the homomorphism rules,
+    /// returning an error if a parameter or condition fails to evaluate.
+    pub fn sample_interpreted(&self, generation: usize) -> Result<State<A>, EvalError> {
+        self.interpret(&self.try_sample(generation)?, generation)
+    }
+
+    /// Apply the homomorphism rules to a state produced at the given generation, returning an
+    /// error if a parameter or condition fails to evaluate.
+    pub fn interpret(&self, state: &State<A>, generation: usize) -> Result<State<A>, EvalError> {
+        let mut rng = self.rng();
+        self.apply(&self.homomorphism, state.clone(), generation, &mut rng, None, |_, _| Ok(()))
+    }
+
+    /// The rule table used to produce the given generation from the state.
+    ///
+    /// Returns an error if the schedule picks a table that doesn't exist.
+    pub fn table(&self, state: &State<A>, generation: usize) -> Result<&Rules<A>, EvalError> {
+        match self.schedule.as_ref().and_then(|x| x.table(generation, state)) {
+            Some(name) => self.tables.get(&name).ok_or_else(|| {
+                EvalError::new(EvalErrorKind::MissingTable(name)).generation(generation)
+            }),
+            None => Ok(&self.rules),
+        }
+    }
+
+    /// Check every generation can be derived by rewriting each module on its own.
+    pub(crate) fn context_free(&self) -> Result<(), Unsupported> {
+        if matches!(self.schedule, Some(Schedule::Callback(_))) {
+            return Err(Unsupported::Callback);
+        }
+        if !self.decomposition.is_empty() {
+            return Err(Unsupported::Decomposition);
+        }
+
+        let rules = || self.tables.values().chain([&self.rules]).flat_map(Rules::iter);
+        if rules().any(|x| !x.previous.is_empty() || !x.next.is_empty()) {
+            return Err(Unsupported::Context);
+        }
+        let is_cut = |x: &Module<A>| (self.roles)(&x.token) == Role::Cut;
+        if self.axiom.inner.iter().any(is_cut) || rules().any(|x| x.tokens.iter().any(is_cut)) {
+            return Err(Unsupported::Cut);
+        }
+        Ok(())
+    }
+
+    /// Apply the decomposition rules to a freshly derived state of the given generation.
+    ///
+    /// Components keep the provenance of the module they were decomposed from.
+    pub(crate) fn decompose<R, E, F>(
+        &self,
+        state: State<A>,
+        generation: usize,
+        rng: &mut R,
+        origins: Option<&mut Vec<Provenance>>,
+        check: F,
+    ) -> Result<State<A>, E>
+    where
+        R: Rng + ?Sized,
+        E: From<EvalError>,
+        F: FnMut(usize, usize) -> Result<(), E>,
+    {
+        self.apply(&self.decomposition, state, generation, rng, origins, check)
+    }
+
+    /// Remove every cut module along with the rest of its branch, and their provenance.
+    pub(crate) fn cut(
+        &self,
+        mut state: State<A>,
+        origins: Option<&mut Vec<Provenance>>,
+    ) -> State<A> {
+        if !state.inner.iter().any(|x| (self.roles)(&x.token) == Role::Cut) {
+            return state;
+        }
+
+        // The depth of the branch being removed, relative to the cut.
+        let mut cutting: Option<usize> = None;
+        let mut keep = Vec::with_capacity(state.inner.len());
+        for module in &state.inner {
+            cutting = match (cutting, (self.roles)(&module.token)) {
+                (None, Role::Cut) => Some(0),
+                (Some(depth), Role::Push) => Some(depth + 1),
+                (Some(0), Role::Pop) => None,
+                (Some(depth), Role::Pop) => Some(depth - 1),
+                (cutting, _) => cutting,
+            };
+            keep.push(cutting.is_none());
+        }
+
+        retain(&mut state.inner, &keep);
+        if let Some(origins) = origins {
+            retain(origins, &keep);
+        }
         state
     }
+
+    /// Repeatedly apply the rules to a state of the given generation until none of them match,
+    /// calling `check` with the number of modules rewritten and produced so far after every
+    /// module of every pass.
+    ///
+    /// Returns an error if the rules still match after `MAX_DEPTH` passes.
+    fn apply<R, E, F>(
+        &self,
+        rules: &Rules<A>,
+        mut state: State<A>,
+        generation: usize,
+        rng: &mut R,
+        mut origins: Option<&mut Vec<Provenance>>,
+        mut check: F,
+    ) -> Result<State<A>, E>
+    where
+        R: Rng + ?Sized,
+        E: From<EvalError>,
+        F: FnMut(usize, usize) -> Result<(), E>,
+    {
+        if rules.is_empty() {
+            return Ok(state);
+        }
+
+        let variables = self.variables(generation);
+        for _ in 0..MAX_DEPTH {
+            let mut changed = false;
+            let mut inner = Vec::with_capacity(state.inner.len());
+            let mut derived = Vec::new();
+            for index in 0..state.inner.len() {
+                changed |= self
+                    .rewrite(rules, &state.inner, index, &variables, rng, &mut inner)
+                    .map_err(|err| err.generation(generation))?
+                    .is_some();
+                if let Some(origins) = origins.as_deref() {
+                    derived.resize(inner.len(), origins[index]);
+                }
+                check(index + 1, inner.len())?;
+            }
+            state.inner = inner;
+            if let Some(origins) = origins.as_deref_mut() {
+                *origins = derived;
+            }
+            if !changed {
+                return Ok(state);
+            }
+        }
+
+        Err(EvalError::new(EvalErrorKind::MaxDepth(MAX_DEPTH))
+            .generation(generation)
+            .into())
+    }
+
+    /// Rewrite the module at `index` pushing its successor onto `out`.
+    ///
+    /// Returns the index of the rule applied, if any.
+    pub(crate) fn rewrite<R: Rng + ?Sized>(
+        &self,
+        rules: &Rules<A>,
+        modules: &[Module<A>],
+        index: usize,
+        variables: &Variables,
+        rng: &mut R,
+        out: &mut Vec<Module<A>>,
+    ) -> Result<Option<usize>, EvalError> {
+        let pattern = &modules[index];
+        let context = Context {
+            previous: &modules[..index],
+            next: &modules[index + 1..],
+            roles: self.roles,
+            ignore: &self.ignore,
+        };
+        match rules
+            .select(pattern, context, variables, rng)
+            .map_err(|err| err.module(index))?
+        {
+            Some((rule, state)) => {
+                out.extend(state.inner);
+                Ok(Some(rule))
+            }
+            None => {
+                out.push(pattern.clone());
+                Ok(None)
+            }
+        }
+    }
+
+    /// The variables available while producing the given generation.
+    pub(crate) fn variables(&self, generation: usize) -> Variables {
+        let mut variables = self.variables.clone();
+        variables.insert("T".to_string(), crate::Value::Num(generation as f32));
+        variables
+    }
+
+    /// The rng used when sampling, seeded if a seed has been set.
+    pub(crate) fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(thread_rng()).expect("failed to seed rng from thread rng"),
+        }
+    }
+
+    /// Call `f` with the given rng, or the rng of the generation if there isn't one.
+    fn with_rng<T>(
+        &self,
+        rng: Option<&mut (dyn RngCore + '_)>,
+        generation: usize,
+        f: impl FnOnce(&mut dyn RngCore) -> T,
+    ) -> T {
+        match rng {
+            Some(rng) => f(rng),
+            None => f(&mut self.generation_rng(generation)),
+        }
+    }
+
+    /// The rng used to produce the given generation, derived from the seed if one has been set.
+    pub(crate) fn generation_rng(&self, generation: usize) -> StdRng {
+        match self.seed {
+            Some(seed) => {
+                StdRng::seed_from_u64(seed ^ (generation as u64).wrapping_mul(GENERATION_GAMMA))
+            }
+            None => self.rng(),
+        }
+    }
+}
+
+/// Keep only the items whose flag in `keep` is set.
+fn retain<T>(items: &mut Vec<T>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    items.retain(|_| keep.next() == Some(&true));
 }
@@ -2,10 +2,29 @@
 
 mod axiom;
 mod builder;
+mod d0l;
+mod dot;
+mod expand;
+mod generations;
+mod limits;
 mod lsystem;
+mod options;
+mod provenance;
+mod schedule;
 mod state;
 
+#[cfg(feature = "parallel")]
+mod parallel;
+
+pub(crate) use self::d0l::Productions;
+
 pub use self::axiom::Axiom;
 pub use self::builder::LSystemBuilder;
+pub use self::expand::Expand;
+pub use self::generations::Generations;
+pub use self::limits::SampleLimits;
 pub use self::lsystem::LSystem;
+pub use self::options::SampleOptions;
+pub use self::provenance::Provenance;
+pub use self::schedule::{Schedule, TableCallback};
 pub use self::state::State;
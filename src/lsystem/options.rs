@@ -0,0 +1,32 @@
+use rand::RngCore;
+
+use crate::{Alphabet, Environment, Provenance, SampleLimits};
+
+/// Settings for `LSystem::sample_with` and `LSystem::step_with`.
+pub struct SampleOptions<'a, A: Alphabet> {
+    /// Draw all randomness from this rng instead of the rng derived for each generation.
+    pub rng: Option<&'a mut dyn RngCore>,
+
+    /// Answers query modules in the axiom and after every generation.
+    pub environment: Option<&'a mut dyn Environment<A>>,
+
+    /// Stop with an error as soon as one of the limits is exceeded.
+    pub limits: SampleLimits<'a>,
+
+    /// Records where every module came from, at the same index as the module in the state.
+    ///
+    /// `LSystem::sample_with` overwrites it, `LSystem::step_with` expects the provenance of the
+    /// given state and replaces it with the provenance of the result.
+    pub provenance: Option<&'a mut Vec<Provenance>>,
+}
+
+impl<A: Alphabet> Default for SampleOptions<'_, A> {
+    fn default() -> Self {
+        Self {
+            rng: None,
+            environment: None,
+            limits: Default::default(),
+            provenance: None,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::{Alphabet, EvalError, LSystem, State};
+
+/// Number of modules rewritten by a single rayon task.
+///
+/// Each chunk gets its own rng, so this is fixed rather than derived from the
+/// thread count to keep seeded derivations reproducible on any machine.
+const CHUNK_SIZE: usize = 4096;
+
+impl<A: Alphabet + Send + Sync> LSystem<A> {
+
+    /// Evaluate this LSystem rewriting each generation on the rayon thread pool, returning an
+    /// error if a parameter or condition fails to evaluate.
+    ///
+    /// Deterministic systems produce the same state as `sample`.
+    pub fn par_sample(&self, generation: usize) -> Result<State<A>, EvalError> {
+        let mut state =
+            self.start(&mut self.generation_rng(0), None, |_, _| Ok::<_, EvalError>(()))?;
+
+        for generation in 1..=generation {
+            state = self.par_step(&state, generation)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Advance the given state by a single generation on the rayon thread pool, returning an
+    /// error if a parameter or condition fails to evaluate.
+    ///
+    /// A single seed is drawn from the rng of the generation and every chunk derives its own
+    /// rng from it. Decomposition rules and cuts are applied serially afterwards.
+    pub fn par_step(&self, state: &State<A>, generation: usize) -> Result<State<A>, EvalError> {
+        let mut rng = self.generation_rng(generation);
+        let variables = self.variables(generation);
+        let rules = self.table(state, generation)?;
+        let seed: u64 = rng.gen();
+
+        let chunks = state
+            .inner
+            .par_chunks(CHUNK_SIZE)
+            .enumerate()
+            .map(|(chunk, modules)| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(chunk as u64));
+                let start = chunk * CHUNK_SIZE;
+                let mut out = Vec::with_capacity(modules.len());
+                for index in start..start + modules.len() {
+                    self.rewrite(rules, &state.inner, index, &variables, &mut rng, &mut out)?;
+                }
+                Ok(out)
+            })
+            .collect::<Result<Vec<_>, EvalError>>()
+            .map_err(|err| err.generation(generation))?;
+
+        let state = State {
+            inner: chunks.into_iter().flatten().collect(),
+        };
+        let state =
+            self.decompose(state, generation, &mut rng, None, |_, _| Ok::<_, EvalError>(()))?;
+        Ok(self.cut(state, None))
+    }
+}
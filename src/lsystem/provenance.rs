@@ -0,0 +1,15 @@
+/// Where a module in a derived state came from.
+///
+/// Modules no rule rewrote keep the rule and generation they were created with, axiom modules
+/// have no rule or parent and were created in generation 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Provenance {
+    /// Index of the rule that produced the module, in declaration order within its rule set.
+    pub rule: Option<usize>,
+
+    /// The generation the module was created in.
+    pub generation: usize,
+
+    /// Index of the module it was derived from in the previous generation.
+    pub parent: Option<usize>,
+}
@@ -0,0 +1,43 @@
+use std::{fmt, sync::Arc};
+
+use crate::{Alphabet, State};
+
+/// Callback picking a rule table from the generation number and the current state.
+pub type TableCallback<A> = Arc<dyn Fn(usize, &State<A>) -> String + Send + Sync>;
+
+/// Decides which named rule table rewrites each generation of a table LSystem.
+///
+/// Sampling fails if the chosen table doesn't exist, an empty `Fixed` schedule always uses the
+/// LSystem's default rules.
+#[derive(Clone)]
+pub enum Schedule<A: Alphabet> {
+    /// Cycle through the named tables, the first being used for generation 1.
+    Fixed(Vec<String>),
+    /// Pick the table from the number of the generation being produced.
+    Generation(fn(usize) -> String),
+    /// Pick the table from the number of the generation being produced and the current state.
+    Callback(TableCallback<A>),
+}
+
+impl<A: Alphabet> Schedule<A> {
+
+    /// The name of the table used to produce the given generation from the state.
+    pub fn table(&self, generation: usize, state: &State<A>) -> Option<String> {
+        match self {
+            Schedule::Fixed(tables) if tables.is_empty() => None,
+            Schedule::Fixed(tables) => Some(tables[(generation.max(1) - 1) % tables.len()].clone()),
+            Schedule::Generation(f) => Some(f(generation)),
+            Schedule::Callback(f) => Some(f(generation, state)),
+        }
+    }
+}
+
+impl<A: Alphabet> fmt::Debug for Schedule<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Fixed(tables) => f.debug_tuple("Fixed").field(tables).finish(),
+            Schedule::Generation(x) => f.debug_tuple("Generation").field(x).finish(),
+            Schedule::Callback(_) => f.debug_tuple("Callback").finish_non_exhaustive(),
+        }
+    }
+}
@@ -24,6 +24,26 @@ impl<A: Alphabet> State<A> {
     pub fn contains(&self, m: &Module<A>) -> bool {
         self.inner.contains(m)
     }
+
+    /// Get the module at the given index.
+    pub fn get(&self, index: usize) -> Option<&Module<A>> {
+        self.inner.get(index)
+    }
+
+    /// Iterate over the modules in this state.
+    pub fn iter(&self) -> std::slice::Iter<'_, Module<A>> {
+        self.inner.iter()
+    }
+
+    /// The number of modules in this state.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check whether this state contains no modules.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
 }
 
 impl<A: Alphabet> Default for State<A> {
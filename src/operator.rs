@@ -7,9 +7,27 @@ pub enum Operator {
     Sub,
     Mul,
     Div,
+    Rem,
     Exponent
 }
 
+impl Operator {
+
+    /// How tightly this operator binds its operands, higher binding tighter.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Sub => 1,
+            Operator::Mul | Operator::Div | Operator::Rem => 2,
+            Operator::Exponent => 4,
+        }
+    }
+
+    /// Whether a chain of this operator groups from the right, like `a ^ b ^ c`.
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Operator::Exponent)
+    }
+}
+
 impl fmt::Display for Operator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -17,6 +35,7 @@ impl fmt::Display for Operator {
             Operator::Sub => write!(f, "-"),
             Operator::Mul => write!(f, "*"),
             Operator::Div => write!(f, "/"),
+            Operator::Rem => write!(f, "%"),
             Operator::Exponent => write!(f, "^")
         }
     }
@@ -0,0 +1,18 @@
+/// The structural role a grammar token plays when resolving rule contexts.
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq, Default)]
+pub enum Role {
+    /// An ordinary symbol.
+    #[default]
+    Symbol,
+    /// Starts a new branch.
+    Push,
+    /// Ends the current branch.
+    Pop,
+    /// A query module whose parameters are set by the `Environment`.
+    Query,
+    /// Removes itself and the rest of the current branch after each generation.
+    Cut,
+}
+
+/// Function mapping a grammar token to its role, set with `LSystemBuilder::roles`.
+pub type Roles<A> = fn(&A) -> Role;
@@ -1,57 +1,94 @@
-use crate::{Conditional, Value, Variables};
+use std::fmt;
 
+use rand::{thread_rng, Rng};
+
+use crate::{Conditional, EvalError, EvalErrorKind, Value, Variables};
+
+/// Boolean expression deciding whether a rule applies.
 #[derive(Debug, PartialEq, Clone)]
-pub enum ConditionalValue {
+pub enum Condition {
+    /// Compare two values.
+    Compare(Value, Conditional, Value),
+    /// A value, holding when it is a non zero number.
     Value(Value),
-    Condition(Box<Condition>),
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
 }
 
-impl ConditionalValue {
-    pub(crate) fn is_true(
+impl Condition {
+
+    /// Evaluate this condition fetching variables from first the parameters or the variables
+    /// argument.
+    ///
+    /// Random functions draw from the thread local rng, see `Condition::evaluate_with_rng`.
+    pub fn evaluate(&self, parameters: &Variables, variables: &Variables) -> Result<bool, EvalError> {
+        self.evaluate_with_rng(parameters, variables, &mut thread_rng())
+    }
+
+    /// Evaluate this condition like `Condition::evaluate`, drawing the result of random
+    /// functions from the given rng.
+    ///
+    /// The right side of `&&` and `||` is only evaluated when needed.
+    pub fn evaluate_with_rng<R: Rng + ?Sized>(
         &self,
         parameters: &Variables,
         variables: &Variables,
-    ) -> Result<bool, &Value> {
+        rng: &mut R,
+    ) -> Result<bool, EvalError> {
         match self {
-            ConditionalValue::Value(val) => Err(val),
-            ConditionalValue::Condition(cond) => Ok(cond.is_true(parameters, variables)),
+            Condition::Compare(a, cond, b) => {
+                let a = a.try_evaluate_with_rng(parameters, variables, rng)?;
+                let b = b.try_evaluate_with_rng(parameters, variables, rng)?;
+                cond.compare(a, b).map_err(|kind| EvalError::new(kind).expr(self))
+            }
+            Condition::Value(val) => match val.try_evaluate_with_rng(parameters, variables, rng)? {
+                Value::Num(x) => Ok(x != 0.0),
+                val => Err(EvalError::new(EvalErrorKind::NonNumericCondition(val)).expr(self)),
+            },
+            Condition::Not(x) => Ok(!x.evaluate_with_rng(parameters, variables, rng)?),
+            Condition::And(a, b) => Ok(a.evaluate_with_rng(parameters, variables, rng)?
+                && b.evaluate_with_rng(parameters, variables, rng)?),
+            Condition::Or(a, b) => Ok(a.evaluate_with_rng(parameters, variables, rng)?
+                || b.evaluate_with_rng(parameters, variables, rng)?),
         }
     }
-}
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Condition {
-    pub a: ConditionalValue,
-    pub cond: Conditional,
-    pub b: ConditionalValue,
+    /// How tightly this condition binds, used to place parentheses when displaying it.
+    fn precedence(&self) -> u8 {
+        match self {
+            Condition::Or(_, _) => 1,
+            Condition::And(_, _) => 2,
+            Condition::Compare(_, _, _) => 3,
+            Condition::Not(_) | Condition::Value(_) => 4,
+        }
+    }
 }
 
-impl Condition {
-    pub(crate) fn is_true(&self, parameters: &Variables, variables: &Variables) -> bool {
-        match self.a.is_true(parameters, variables) {
-            Ok(false) => false,
-            Ok(true) => match self.cond {
-                Conditional::And => self.b.is_true(parameters, variables).unwrap_or(false),
-                Conditional::Or => true,
-                Conditional::EqualTo => self.b.is_true(parameters, variables).unwrap_or(false),
-                Conditional::GreaterThan => panic!("Called Greater than on boolean"),
-                Conditional::LessThan => panic!("Called Less than on boolean"),
-            },
-            Err(val) => {
-                //let a = val.evaluate(parameters, variables);
-                match self.b.is_true(parameters, variables) {
-                    Err(val_b) => match self.cond {
-                        Conditional::And => val == val_b,
-                        Conditional::Or => {
-                            panic!("Calling or with a value argument is unsupported")
-                        }
-                        Conditional::EqualTo => val == val_b,
-                        Conditional::GreaterThan => val > val_b,
-                        Conditional::LessThan => val < val_b,
-                    },
-                    Ok(_) => panic!("Attempting to compare value and boolean"),
-                }
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Compare(a, cond, b) => write!(f, "{a} {cond} {b}"),
+            Condition::Value(val) => write!(f, "{val}"),
+            Condition::Not(x) => {
+                write!(f, "!")?;
+                operand(f, x, x.precedence() < self.precedence())
+            }
+            Condition::And(a, b) | Condition::Or(a, b) => {
+                let op = if matches!(self, Condition::And(_, _)) { "&&" } else { "||" };
+                operand(f, a, a.precedence() < self.precedence())?;
+                write!(f, " {op} ")?;
+                operand(f, b, b.precedence() <= self.precedence())
             }
         }
     }
 }
+
+/// Write an operand of a condition, wrapped in parentheses if needed.
+fn operand(f: &mut fmt::Formatter<'_>, condition: &Condition, parens: bool) -> fmt::Result {
+    if parens {
+        write!(f, "({condition})")
+    } else {
+        write!(f, "{condition}")
+    }
+}
@@ -1,9 +1,51 @@
-/// Conditional used when elvaluating LSystem rules.
+use std::fmt;
+
+use crate::{EvalErrorKind, Value};
+
+/// Comparison used when elvaluating LSystem rules.
 #[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
 pub enum Conditional {
-    Or,
-    And,
     EqualTo,
+    NotEqual,
     GreaterThan,
+    GreaterOrEqual,
     LessThan,
+    LessOrEqual,
+}
+
+impl Conditional {
+
+    /// Compare two evaluated values.
+    ///
+    /// Any values can be checked for equality, ordering requires both to be numbers.
+    pub(crate) fn compare(&self, a: Value, b: Value) -> Result<bool, EvalErrorKind> {
+        let (x, y) = match (self, a, b) {
+            (Conditional::EqualTo, a, b) => return Ok(a == b),
+            (Conditional::NotEqual, a, b) => return Ok(a != b),
+            (_, Value::Num(x), Value::Num(y)) => (x, y),
+            (_, Value::Num(_), val) | (_, val, _) => {
+                return Err(EvalErrorKind::NonNumericComparison(*self, val))
+            }
+        };
+        Ok(match self {
+            Conditional::GreaterThan => x > y,
+            Conditional::GreaterOrEqual => x >= y,
+            Conditional::LessThan => x < y,
+            Conditional::LessOrEqual => x <= y,
+            Conditional::EqualTo | Conditional::NotEqual => unreachable!(),
+        })
+    }
+}
+
+impl fmt::Display for Conditional {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conditional::EqualTo => write!(f, "=="),
+            Conditional::NotEqual => write!(f, "!="),
+            Conditional::GreaterThan => write!(f, ">"),
+            Conditional::GreaterOrEqual => write!(f, ">="),
+            Conditional::LessThan => write!(f, "<"),
+            Conditional::LessOrEqual => write!(f, "<="),
+        }
+    }
 }
@@ -4,8 +4,11 @@ mod condition;
 mod conditional;
 mod rule;
 mod rules;
+mod selection;
 
-pub use self::condition::{ConditionalValue, Condition};
+pub use self::condition::Condition;
 pub use self::conditional::Conditional;
 pub use self::rule::Rule;
-pub use self::rules::Rules;
+pub use self::rules::{RuleKey, Rules};
+pub(crate) use self::rules::hash_key;
+pub use self::selection::Selection;
@@ -1,15 +1,15 @@
-use std::collections::HashMap;
-use rand::{thread_rng, Rng};
+use std::{collections::HashMap, fmt};
+use rand::Rng;
 
-use crate::{Alphabet, Condition, Context, Module, State, Value, Variables};
+use crate::{Alphabet, Condition, Context, EvalError, Module, State, Value, Variables};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Rule<A: Alphabet> {
     pub pattern: Module<A>,
     pub tokens: State<A>,
     pub probability: f32,
-    pub previous: Option<A>,
-    pub next: Option<A>,
+    pub previous: Vec<Module<A>>,
+    pub next: Vec<Module<A>>,
     pub condition: Option<Condition>,
 }
 
@@ -27,18 +27,20 @@ impl<A: Alphabet> Rule<A> {
             },
             pattern: pattern.into(),
             probability: 1.0,
-            next: None,
-            previous: None,
+            next: Default::default(),
+            previous: Default::default(),
             condition: None,
         }
     }
-    pub fn with_previous(mut self, prev: Option<A>) -> Self {
-        self.previous = prev;
+    /// Set the modules that must come directly before the predecessor.
+    pub fn with_previous<I: Into<Module<A>>, Iter: IntoIterator<Item = I>>(mut self, prev: Iter) -> Self {
+        self.previous = prev.into_iter().map(|x| x.into()).collect();
         self
     }
 
-    pub fn with_next(mut self, next: Option<A>) -> Self {
-        self.next = next;
+    /// Set the modules that must come directly after the predecessor.
+    pub fn with_next<I: Into<Module<A>>, Iter: IntoIterator<Item = I>>(mut self, next: Iter) -> Self {
+        self.next = next.into_iter().map(|x| x.into()).collect();
         self
     }
 
@@ -50,71 +52,145 @@ impl<A: Alphabet> Rule<A> {
         self.condition = condition;
         self
     }
-    pub fn match_pattern(
+    /// Check this rule applies to the given module, rolling its probability.
+    ///
+    /// Returns the bound parameters if the rule should be applied.
+    pub fn match_pattern<R: Rng + ?Sized>(
+        &self,
+        pattern: &Module<A>,
+        context: &Context<'_, A>,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<Option<Variables>, EvalError> {
+        Ok(self
+            .matches(pattern, context, variables, rng)?
+            .filter(|_| rng.gen_range(0.0..1.0) < self.probability))
+    }
+
+    /// Check the predecessor, context and condition of this rule ignoring its probability.
+    ///
+    /// Returns the bound parameters if the rule matches.
+    pub fn matches<R: Rng + ?Sized>(
         &self,
         pattern: &Module<A>,
         context: &Context<'_, A>,
-        params: &Variables,
         variables: &Variables,
-    ) -> bool {
-        self.has_prefix(context)
-            && self.has_suffix(context)
-            && pattern.token == self.pattern.token
-            && thread_rng().gen_range(0.0..1.0) < self.probability
-            && self
-                .condition
-                .as_ref()
-                .map(|x| x.is_true(params, variables))
-                .unwrap_or(true)
+        rng: &mut R,
+    ) -> Result<Option<Variables>, EvalError> {
+        let Some(params) = self.params(pattern, context) else {
+            return Ok(None);
+        };
+        let is_true = match &self.condition {
+            Some(condition) => condition.evaluate_with_rng(&params, variables, rng)?,
+            None => true,
+        };
+        Ok(is_true.then_some(params))
     }
 
-    pub fn produce(&self, params: &Variables, variables: &Variables) -> State<A> {
+    /// Whether both rules share a predecessor, context and condition.
+    ///
+    /// Predecessors are compared by token and number of parameters, contexts by token, so
+    /// rules binding their parameters to different names still form a group.
+    pub(crate) fn same_group(&self, other: &Rule<A>) -> bool {
+        let tokens = |a: &[Module<A>], b: &[Module<A>]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.token == b.token)
+        };
+        self.pattern.token == other.pattern.token
+            && self.pattern.params.len() == other.pattern.params.len()
+            && tokens(&self.previous, &other.previous)
+            && tokens(&self.next, &other.next)
+            && self.condition == other.condition
+    }
+
+    /// Evaluate the successor of this rule with the bound parameters, random functions
+    /// drawing from the given rng.
+    pub fn produce<R: Rng + ?Sized>(
+        &self,
+        params: &Variables,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<State<A>, EvalError> {
         let inner = self
             .tokens
-            .clone()
             .inner
-            .into_iter()
-            .map(|mut x| {
-                x.params = x
+            .iter()
+            .map(|x| {
+                let params = x
                     .params
-                    .into_iter()
-                    .map(|x| x.evaluate(params, variables))
-                    .collect();
-                x
+                    .iter()
+                    .map(|x| {
+                        x.try_evaluate_with_rng(params, variables, rng)
+                            .map_err(|err| err.expr(x))
+                    })
+                    .collect::<Result<_, EvalError>>()?;
+                Ok(Module::new(x.token).params(params))
             })
-            .collect();
+            .collect::<Result<_, EvalError>>()?;
 
-        State { inner }
+        Ok(State { inner })
     }
 
-    fn has_prefix(&self, ctx: &Context<'_, A>) -> bool {
-        if let Some(_pre) = self.previous {
-            return Some(_pre) == ctx.previous.map(|x| x.token);
+    /// Match the predecessor and its context binding the parameters of every matched module.
+    pub(crate) fn params(&self, caller: &Module<A>, context: &Context<'_, A>) -> Option<Variables> {
+        if caller.token != self.pattern.token {
+            return None;
         }
-        true
-    }
+        let previous = context.left(&self.previous)?;
+        let next = context.right(&self.next)?;
 
-    fn has_suffix(&self, ctx: &Context<'_, A>) -> bool {
-        if let Some(_pre) = self.next {
-            return Some(_pre) == ctx.next.map(|x| x.token);
+        let mut params = HashMap::with_capacity(self.pattern.params.len());
+        bind(&mut params, &self.pattern, caller);
+        for (pattern, module) in self.previous.iter().zip(previous) {
+            bind(&mut params, pattern, module);
+        }
+        for (pattern, module) in self.next.iter().zip(next) {
+            bind(&mut params, pattern, module);
         }
-        true
+
+        Some(params)
     }
+}
 
-    pub(crate) fn params(&self, caller: &Module<A>) -> Variables {
-        let mut params = HashMap::with_capacity(self.pattern.params.len());
+/// Bind each variable in the pattern to the parameter at the same position in the module.
+fn bind<A: Alphabet>(params: &mut Variables, pattern: &Module<A>, module: &Module<A>) {
+    for (p, value) in pattern.params.iter().zip(&module.params) {
+        if let Value::Var(n) = p {
+            params.insert(n.clone(), value.clone());
+        }
+    }
+}
 
-        for (dex, p) in self.pattern.params.iter().enumerate() {
-            match p {
-                Value::Var(n) => {
-                    if caller.params.len() > dex {
-                        params.insert(*n, caller.params[dex].clone());
-                    }
-                }
-                _ => unreachable!(),
-            }
+/// Rules are written in the grammar syntax, like `B < A(x) > C : x > 1 : 0.5 -> A(x + 1)B`.
+impl<A: Alphabet + fmt::Display> fmt::Display for Rule<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.previous.is_empty() {
+            modules(f, &self.previous)?;
+            write!(f, " < ")?;
+        }
+        modules(f, std::slice::from_ref(&self.pattern))?;
+        if !self.next.is_empty() {
+            write!(f, " > ")?;
+            modules(f, &self.next)?;
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " : {condition}")?;
+        }
+        if self.probability != 1.0 {
+            write!(f, " : {}", self.probability)?;
         }
+        write!(f, " -> ")?;
+        modules(f, &self.tokens.inner)
+    }
+}
 
-        params
+/// Write each module's token followed by its parameters.
+fn modules<A: Alphabet + fmt::Display>(f: &mut fmt::Formatter<'_>, modules: &[Module<A>]) -> fmt::Result {
+    for module in modules {
+        write!(f, "{}", module.token)?;
+        if !module.params.is_empty() {
+            let params = module.params.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            write!(f, "({})", params.join(", "))?;
+        }
     }
+    Ok(())
 }
@@ -1,35 +1,182 @@
-use crate::{Alphabet, Context, Module, Rule, State, Variables};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
 
-#[derive(Debug, PartialEq, Clone)]
+use rand::Rng;
+
+use crate::{Alphabet, Context, EvalError, Module, Rule, Selection, State, Variables};
+
+/// Function mapping a predecessor token to the bucket its rules are stored in.
+pub type RuleKey<A> = fn(&A) -> u64;
+
+#[derive(Debug, Clone)]
 pub struct Rules<A: Alphabet> {
     inner: Vec<Rule<A>>,
+    pub selection: Selection,
+    key: RuleKey<A>,
+    index: HashMap<u64, Vec<usize>>,
 }
 
 impl<A: Alphabet> Default for Rules<A> {
     fn default() -> Self {
         Self {
             inner: Default::default(),
+            selection: Default::default(),
+            key: |_| 0,
+            index: Default::default(),
         }
     }
 }
 
+impl<A: Alphabet> PartialEq for Rules<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.selection == other.selection
+    }
+}
+
+/// Key rules by the hash of their predecessor token.
+pub(crate) fn hash_key<A: Hash>(token: &A) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
 impl<A: Alphabet> Rules<A> {
     pub fn append(&mut self, rule: Rule<A>) {
+        let key = (self.key)(&rule.pattern.token);
+        self.index.entry(key).or_default().push(self.inner.len());
         self.inner.push(rule);
     }
 
-    pub fn process(
+    /// Check whether there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over the rules in declaration order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Rule<A>> {
+        self.inner.iter()
+    }
+
+    /// Index rules by the given key so only rules sharing a key with a module are checked.
+    ///
+    /// Tokens that are equal must produce the same key, rules keep their declaration order
+    /// within each key.
+    pub fn index_by(&mut self, key: RuleKey<A>) {
+        self.key = key;
+        self.index.clear();
+        for (dex, rule) in self.inner.iter().enumerate() {
+            self.index.entry(key(&rule.pattern.token)).or_default().push(dex);
+        }
+    }
+
+    /// The indices of every rule that could match the given token, in declaration order.
+    fn candidates(&self, token: &A) -> &[usize] {
+        self.index
+            .get(&(self.key)(token))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Rewrite a module with the first matching rule, returning `None` if no rule applies.
+    ///
+    /// Errors carry the index of the rule that failed.
+    pub fn process<R: Rng + ?Sized>(
         &self,
         pattern: &Module<A>,
         context: Context<'_, A>,
         variables: &Variables,
-    ) -> Option<State<A>> {
-        for rule in &self.inner {
-            let params = rule.params(pattern);
-            if rule.match_pattern(pattern, &context, &params, variables) {
-                return Some(rule.produce(&params, variables));
+        rng: &mut R,
+    ) -> Result<Option<State<A>>, EvalError> {
+        Ok(self
+            .select(pattern, context, variables, rng)?
+            .map(|(_, state)| state))
+    }
+
+    /// Rewrite a module like `process`, also returning the index of the rule applied.
+    pub(crate) fn select<R: Rng + ?Sized>(
+        &self,
+        pattern: &Module<A>,
+        context: Context<'_, A>,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<Option<(usize, State<A>)>, EvalError> {
+        match self.selection {
+            Selection::Weighted => self.process_weighted(pattern, context, variables, rng),
+            Selection::Sequential => self.process_sequential(pattern, context, variables, rng),
+        }
+    }
+
+    fn process_sequential<R: Rng + ?Sized>(
+        &self,
+        pattern: &Module<A>,
+        context: Context<'_, A>,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<Option<(usize, State<A>)>, EvalError> {
+        for dex in self.candidates(&pattern.token) {
+            let rule = &self.inner[*dex];
+            let produced = rule
+                .match_pattern(pattern, &context, variables, rng)
+                .and_then(|x| x.map(|params| rule.produce(&params, variables, rng)).transpose())
+                .map_err(|err| err.rule(*dex))?;
+            if let Some(state) = produced {
+                return Ok(Some((*dex, state)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn process_weighted<R: Rng + ?Sized>(
+        &self,
+        pattern: &Module<A>,
+        context: Context<'_, A>,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<Option<(usize, State<A>)>, EvalError> {
+        let candidates = self.candidates(&pattern.token);
+        for (pos, dex) in candidates.iter().enumerate() {
+            let rule = &self.inner[*dex];
+            let Some(params) = rule
+                .matches(pattern, &context, variables, rng)
+                .map_err(|err| err.rule(*dex))?
+            else {
+                continue;
+            };
+
+            // Any earlier rule in the same group would already have matched.
+            let group: Vec<usize> = candidates[pos..]
+                .iter()
+                .copied()
+                .filter(|x| self.inner[*x].same_group(rule))
+                .collect();
+            let total: f32 = group.iter().map(|x| self.inner[*x].probability).sum();
+            if total <= 0.0 {
+                continue;
+            }
+
+            let mut chosen = *dex;
+            if group.len() > 1 {
+                let mut roll = rng.gen_range(0.0..total);
+                for candidate in &group {
+                    chosen = *candidate;
+                    if roll < self.inner[*candidate].probability {
+                        break;
+                    }
+                    roll -= self.inner[*candidate].probability;
+                }
             }
+            // The chosen rule may bind the parameters to different names.
+            let params = match chosen == *dex {
+                true => params,
+                false => self.inner[chosen].params(pattern, &context).unwrap_or(params),
+            };
+            return self.inner[chosen]
+                .produce(&params, variables, rng)
+                .map(|state| Some((chosen, state)))
+                .map_err(|err| err.rule(chosen));
         }
-        None
+        Ok(None)
     }
 }
@@ -0,0 +1,11 @@
+/// How a production is chosen when several rules match the same module.
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq, Default)]
+pub enum Selection {
+    /// Rules sharing a predecessor, context and condition form a group, their
+    /// probabilities are normalized and exactly one of them is picked.
+    #[default]
+    Weighted,
+    /// Rules are tried in declaration order and each rolls its own probability,
+    /// the first rule to succeed is applied.
+    Sequential,
+}
@@ -1,54 +1,165 @@
 use std::{fmt, ops};
 
-use crate::{Operator, Variables};
+use rand::{thread_rng, Rng};
+
+use crate::{EvalError, EvalErrorKind, Function, Operator, Variables};
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum Value {
     Num(f32),
-    Var(char),
+    Var(String),
     Color(f32, f32, f32, f32),
     Expr(Box<Self>, Operator, Box<Self>),
+    Neg(Box<Self>),
+    Call(Function, Vec<Self>),
 }
 
+/// How tightly unary minus binds, tighter than `*` but looser than `^`.
+pub(crate) const NEG_PRECEDENCE: u8 = 3;
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Num(x) => write!(f, "{x}"),
             Value::Var(x) => write!(f, "{x}"),
             Value::Color(r, g, b, a) => write!(f, "rgba({r}, {g}, {b}, {a})"),
-            Value::Expr(a, op, b) => write!(f, "{a} {op} {b}"),
+            Value::Expr(a, op, b) => {
+                let right = op.is_right_associative();
+                let precedence = op.precedence();
+                operand(f, a, a.precedence() < precedence || (right && a.precedence() == precedence))?;
+                write!(f, " {op} ")?;
+                operand(f, b, b.precedence() < precedence || (!right && b.precedence() == precedence))
+            }
+            Value::Neg(x) => {
+                write!(f, "-")?;
+                operand(f, x, x.precedence() < NEG_PRECEDENCE)
+            }
+            Value::Call(func, args) => {
+                write!(f, "{func}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
+/// Write an operand of an expression, wrapping it in parentheses if needed.
+fn operand(f: &mut fmt::Formatter<'_>, value: &Value, parens: bool) -> fmt::Result {
+    if parens {
+        write!(f, "({value})")
+    } else {
+        write!(f, "{value}")
+    }
+}
+
 impl Value {
 
+    /// Whether evaluating this value calls a random function.
+    pub fn is_random(&self) -> bool {
+        match self {
+            Value::Expr(a, _, b) => a.is_random() || b.is_random(),
+            Value::Neg(x) => x.is_random(),
+            Value::Call(func, args) => func.is_random() || args.iter().any(Value::is_random),
+            Value::Num(_) | Value::Var(_) | Value::Color(..) => false,
+        }
+    }
+
     /// Evaluate this value fetching variables from first the parameters or the variables
     /// argument.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be evaluated, see `Value::try_evaluate`.
     pub fn evaluate(&self, parameters: &Variables, variables: &Variables) -> Self {
+        self.try_evaluate(parameters, variables)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Evaluate this value fetching variables from first the parameters or the variables
+    /// argument, returning an error if an operator is applied to a non numeric value.
+    ///
+    /// Random functions draw from the thread local rng, see `Value::try_evaluate_with_rng`.
+    pub fn try_evaluate(&self, parameters: &Variables, variables: &Variables) -> Result<Self, EvalError> {
+        self.try_evaluate_with_rng(parameters, variables, &mut thread_rng())
+    }
+
+    /// Evaluate this value like `Value::try_evaluate`, drawing the result of random functions
+    /// from the given rng.
+    pub fn try_evaluate_with_rng<R: Rng + ?Sized>(
+        &self,
+        parameters: &Variables,
+        variables: &Variables,
+        rng: &mut R,
+    ) -> Result<Self, EvalError> {
         match self {
             Value::Var(x) => {
                 if let Some(a) = parameters.get(x).cloned() {
-                    a
+                    Ok(a)
                 } else if let Some(b) = variables.get(x).cloned() {
-                    b
+                    Ok(b)
                 } else {
-                    Value::Var(*x)
+                    Ok(self.clone())
                 }
             }
             Value::Expr(a, op, b) => {
-                let a = a.evaluate(parameters, variables);
-                let b = b.evaluate(parameters, variables);
-                // TODO: Remove these unwraps.
-                match op {
-                    Operator::Add => a + b,
-                    Operator::Sub => a - b,
-                    Operator::Div => a / b,
-                    Operator::Mul => a * b,
-                    Operator::Exponent => Value::Num(a.to_float().unwrap().powf(b.to_float().unwrap()))
+                let a = a.try_evaluate_with_rng(parameters, variables, rng)?;
+                let b = b.try_evaluate_with_rng(parameters, variables, rng)?;
+                a.apply(op, b).map_err(|err| err.expr(self))
+            }
+            Value::Neg(x) => match x.try_evaluate_with_rng(parameters, variables, rng)? {
+                Value::Num(x) => Ok(Value::Num(-x)),
+                val => Err(EvalError::new(EvalErrorKind::NonNumeric(Operator::Sub, val)).expr(self)),
+            },
+            Value::Call(func, args) => {
+                if args.len() != func.arity() {
+                    return Err(EvalError::new(EvalErrorKind::Arity(*func, args.len())).expr(self));
                 }
+                let args = args
+                    .iter()
+                    .map(|x| match x.try_evaluate_with_rng(parameters, variables, rng)? {
+                        Value::Num(x) => Ok(x),
+                        val => Err(EvalError::new(EvalErrorKind::NonNumericArgument(*func, val))
+                            .expr(self)),
+                    })
+                    .collect::<Result<Vec<_>, EvalError>>()?;
+                Ok(Value::Num(func.call(&args, rng)))
             }
-            s => s.clone(),
+            s => Ok(s.clone()),
+        }
+    }
+
+    /// Apply an operator to this value and another.
+    ///
+    /// Returns an error if either value is not `Value::Num`.
+    pub fn apply(self, op: &Operator, rhs: Self) -> Result<Self, EvalError> {
+        let (a, b) = match (self, rhs) {
+            (Value::Num(a), Value::Num(b)) => (a, b),
+            (Value::Num(_), val) | (val, _) => {
+                return Err(EvalErrorKind::NonNumeric(op.clone(), val).into())
+            }
+        };
+        Ok(Value::Num(match op {
+            Operator::Add => a + b,
+            Operator::Sub => a - b,
+            Operator::Mul => a * b,
+            Operator::Div => a / b,
+            Operator::Rem => a % b,
+            Operator::Exponent => a.powf(b),
+        }))
+    }
+
+    /// How tightly this value binds when written as an operand, used to place parentheses.
+    fn precedence(&self) -> u8 {
+        match self {
+            Value::Expr(_, op, _) => op.precedence(),
+            Value::Neg(_) => NEG_PRECEDENCE,
+            Value::Num(x) if x.is_sign_negative() => NEG_PRECEDENCE,
+            _ => u8::MAX,
         }
     }
 
@@ -68,15 +179,7 @@ impl ops::Add for Value {
     type Output = Value;
 
     fn add(self, rhs: Self) -> Self::Output {
-        if let Value::Num(a) = self {
-            if let Value::Num(b) = rhs {
-                Value::Num(a + b)
-            } else {
-                panic!("attempted to add non numeric value {rhs:?}");
-            }
-        } else {
-            panic!("attempted to add non numeric value {self:?}");
-        }
+        self.apply(&Operator::Add, rhs).unwrap_or_else(|err| panic!("{err}"))
     }
 }
 
@@ -84,15 +187,7 @@ impl ops::Sub for Value {
     type Output = Value;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        if let Value::Num(a) = self {
-            if let Value::Num(b) = rhs {
-                Value::Num(a - b)
-            } else {
-                panic!("attempted to subtract non numeric value {rhs:?}");
-            }
-        } else {
-            panic!("attempted to subtract non numeric value {self:?}");
-        }
+        self.apply(&Operator::Sub, rhs).unwrap_or_else(|err| panic!("{err}"))
     }
 }
 
@@ -100,15 +195,7 @@ impl ops::Mul for Value {
     type Output = Value;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        if let Value::Num(a) = self {
-            if let Value::Num(b) = rhs {
-                Value::Num(a * b)
-            } else {
-                panic!("attempted to multiply non numeric value {rhs:?}");
-            }
-        } else {
-            panic!("attempted to multiply non numeric value {self:?}");
-        }
+        self.apply(&Operator::Mul, rhs).unwrap_or_else(|err| panic!("{err}"))
     }
 }
 
@@ -116,14 +203,6 @@ impl ops::Div for Value {
     type Output = Value;
 
     fn div(self, rhs: Self) -> Self::Output {
-        if let Value::Num(a) = self {
-            if let Value::Num(b) = rhs {
-                Value::Num(a / b)
-            } else {
-                panic!("attempted to divide non numeric value {rhs:?}");
-            }
-        } else {
-            panic!("attempted to divide non numeric value {self:?}");
-        }
+        self.apply(&Operator::Div, rhs).unwrap_or_else(|err| panic!("{err}"))
     }
 }
@@ -1,7 +1,9 @@
-use hyperion::{LSystemBuilder, Rule, State};
+use std::collections::HashMap;
+
+use hyperion::{LSystemBuilder, Rule, Selection, State, Unsupported};
 use pretty_assertions::assert_eq;
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd)]
 pub enum Algea {
     A,
     B,
@@ -35,3 +37,98 @@ fn generations() {
         ])
     );
 }
+
+#[test]
+fn generations_iter() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    for (generation, state) in lsys.generations().take(8).enumerate() {
+        assert_eq!(state, Ok(lsys.sample(generation)));
+    }
+}
+
+#[test]
+fn step() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    let state = lsys.sample(3);
+    assert_eq!(lsys.step(&state, 4), Ok(lsys.sample(4)));
+    assert_eq!(lsys.step(&State::new([B]), 1), Ok(State::new([A])));
+}
+
+#[test]
+fn symbol_counts() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    assert_eq!(lsys.symbol_counts(0), Ok(HashMap::from([(A, 1)])));
+    assert_eq!(lsys.symbol_counts(5), Ok(HashMap::from([(A, 8), (B, 5)])));
+    assert_eq!(
+        lsys.symbol_counts(100),
+        Ok(HashMap::from([
+            (A, 573147844013817084101),
+            (B, 354224848179261915075),
+        ]))
+    );
+}
+
+#[test]
+fn symbol_counts_stochastic() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]).with_probability(0.5))
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .build();
+    assert_eq!(lsys.symbol_counts(3), Err(Unsupported::Stochastic));
+}
+
+#[test]
+fn symbol_counts_selection() {
+    use Algea::*;
+    let weighted = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]).with_probability(0.5))
+        .rule(Rule::new(B, [A]))
+        .rule(Rule::new(B, [B]).with_probability(0.0))
+        .build();
+    assert_eq!(weighted.symbol_counts(5), Ok(HashMap::from([(A, 8), (B, 5)])));
+    assert_eq!(weighted.sample(5).len(), 13);
+
+    let sequential = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]).with_probability(0.5))
+        .rule(Rule::new(B, [A]))
+        .selection(Selection::Sequential)
+        .build();
+    assert_eq!(sequential.symbol_counts(5), Err(Unsupported::Stochastic));
+
+    let shadowed = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(B, [A]))
+        .selection(Selection::Sequential)
+        .build();
+    assert_eq!(shadowed.symbol_counts(5), Ok(HashMap::from([(A, 8), (B, 5)])));
+    assert_eq!(shadowed.sample(5).len(), 13);
+}
+
+#[test]
+fn symbol_counts_overflow() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A]).rule(Rule::new(A, [A, A])).build();
+    assert_eq!(lsys.symbol_counts(127), Ok(HashMap::from([(A, 1 << 127)])));
+    assert_eq!(lsys.symbol_counts(128), Err(Unsupported::Overflow));
+    assert_eq!(lsys.symbol_counts(200), Err(Unsupported::Overflow));
+
+    let unreachable = LSystemBuilder::new([B])
+        .rule(Rule::new(A, [A, A]))
+        .rule(Rule::new(B, [B]))
+        .build();
+    assert_eq!(unreachable.symbol_counts(200), Ok(HashMap::from([(B, 1)])));
+}
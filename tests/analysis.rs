@@ -0,0 +1,72 @@
+use hyperion::{
+    analysis::{Analysis, Growth},
+    LSystemBuilder, Rule, Unsupported,
+};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbol {
+    A,
+    B,
+    C,
+    D,
+}
+
+#[test]
+fn exponential() {
+    use Symbol::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    let analysis = Analysis::new(&lsys).unwrap();
+    let golden = (1.0 + 5f64.sqrt()) / 2.0;
+    assert_eq!(analysis.symbols, vec![A, B]);
+    assert_eq!(analysis.matrix, vec![vec![1, 1], vec![1, 0]]);
+    assert!((analysis.eigenvalue - golden).abs() < 1e-9);
+    match analysis.growth {
+        Growth::Exponential(rate) => assert!((rate - golden).abs() < 1e-9),
+        growth => panic!("expected exponential growth, found {growth:?}"),
+    }
+    assert_eq!(analysis.dead, vec![]);
+    assert_eq!(analysis.unreachable, vec![]);
+}
+
+#[test]
+fn polynomial() {
+    use Symbol::*;
+    let linear = LSystemBuilder::new([A]).rule(Rule::new(A, [A, B])).build();
+    assert_eq!(Analysis::new(&linear).unwrap().growth, Growth::Polynomial(1));
+
+    let quadratic = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [B, C]))
+        .build();
+    let analysis = Analysis::new(&quadratic).unwrap();
+    assert_eq!(analysis.growth, Growth::Polynomial(2));
+    assert_eq!(analysis.eigenvalue, 1.0);
+}
+
+#[test]
+fn bounded_dead_and_unreachable() {
+    use Symbol::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, D]))
+        .rule(Rule::new(D, [] as [Symbol; 0]))
+        .rule(Rule::new(C, [C, C]))
+        .build();
+    let analysis = Analysis::new(&lsys).unwrap();
+    assert_eq!(analysis.growth, Growth::Bounded);
+    assert_eq!(analysis.eigenvalue, 2.0);
+    assert_eq!(analysis.dead, vec![D]);
+    assert_eq!(analysis.unreachable, vec![C]);
+}
+
+#[test]
+fn context() {
+    use Symbol::*;
+    let lsys = LSystemBuilder::new([A, B])
+        .rule(Rule::new(B, [B, B]).with_previous([A]))
+        .build();
+    assert_eq!(Analysis::new(&lsys), Err(Unsupported::Context));
+}
@@ -1,7 +1,7 @@
 use hyperion::{LSystemBuilder, Rule, State};
 use pretty_assertions::assert_eq;
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
 pub enum BinaryTree {
     One,
     Zero,
@@ -33,3 +33,18 @@ fn generations() {
         ])
     );
 }
+
+#[test]
+fn indexed() {
+    use BinaryTree::*;
+    let lsys = LSystemBuilder::new([Zero])
+        .rule(Rule::new(One, [One, One]))
+        .rule(Rule::new(Zero, [One, Push, Zero, Pop, Zero]));
+    let linear = lsys.clone().build();
+    let hashed = lsys.clone().indexed().build();
+    let keyed = lsys.index_by(|x| *x as u64).build();
+    for generation in 0..6 {
+        assert_eq!(hashed.sample(generation), linear.sample(generation));
+        assert_eq!(keyed.sample(generation), linear.sample(generation));
+    }
+}
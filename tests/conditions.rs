@@ -0,0 +1,146 @@
+#![cfg(feature = "grammar")]
+
+use std::collections::HashMap;
+
+use hyperion::{
+    grammar::parser::{parse_condition, parse_rule},
+    Condition, Conditional, LSystemBuilder, Value,
+};
+use pretty_assertions::assert_eq;
+
+fn num(x: f32) -> Value {
+    Value::Num(x)
+}
+
+fn var(x: &str) -> Value {
+    Value::Var(x.to_string())
+}
+
+fn cmp(a: Value, cond: Conditional, b: Value) -> Condition {
+    Condition::Compare(a, cond, b)
+}
+
+fn and(a: Condition, b: Condition) -> Condition {
+    Condition::And(Box::new(a), Box::new(b))
+}
+
+fn or(a: Condition, b: Condition) -> Condition {
+    Condition::Or(Box::new(a), Box::new(b))
+}
+
+fn not(x: Condition) -> Condition {
+    Condition::Not(Box::new(x))
+}
+
+fn parse(input: &str) -> Condition {
+    let (rest, condition) = parse_condition(input).unwrap();
+    assert_eq!(rest, "", "unparsed input after `{input}`");
+    condition
+}
+
+fn eval(input: &str) -> bool {
+    let variables = HashMap::from([
+        ("t".to_string(), num(4.0)),
+        ("w".to_string(), num(0.2)),
+        ("leaf".to_string(), num(0.0)),
+    ]);
+    parse(input).evaluate(&HashMap::new(), &variables).unwrap()
+}
+
+#[test]
+fn comparisons() {
+    use Conditional::*;
+    assert_eq!(parse(": a >= 3"), cmp(var("a"), GreaterOrEqual, num(3.0)));
+    assert_eq!(parse(": a <= 3"), cmp(var("a"), LessOrEqual, num(3.0)));
+    assert_eq!(parse(": a == 3"), cmp(var("a"), EqualTo, num(3.0)));
+    assert_eq!(parse(": a = 3"), cmp(var("a"), EqualTo, num(3.0)));
+    assert_eq!(parse(": a != -3"), cmp(var("a"), NotEqual, num(-3.0)));
+    assert_eq!(parse(":a>b"), cmp(var("a"), GreaterThan, var("b")));
+    assert_eq!(parse(": a < b"), cmp(var("a"), LessThan, var("b")));
+}
+
+#[test]
+fn logic() {
+    use Conditional::*;
+    assert_eq!(
+        parse(": t >= 3 && (w < 0.1 || !leaf)"),
+        and(
+            cmp(var("t"), GreaterOrEqual, num(3.0)),
+            or(cmp(var("w"), LessThan, num(0.1)), not(Condition::Value(var("leaf")))),
+        )
+    );
+    assert_eq!(
+        parse(": a || b && c"),
+        or(Condition::Value(var("a")), and(Condition::Value(var("b")), Condition::Value(var("c"))))
+    );
+    assert_eq!(
+        parse(": a > 1 & b < 2 | c"),
+        or(
+            and(cmp(var("a"), GreaterThan, num(1.0)), cmp(var("b"), LessThan, num(2.0))),
+            Condition::Value(var("c")),
+        )
+    );
+    assert_eq!(
+        parse(": !(a == 1 || b)"),
+        not(or(cmp(var("a"), EqualTo, num(1.0)), Condition::Value(var("b"))))
+    );
+    assert_eq!(parse(": (a + 1) * 2 > b"), parse(": ((a + 1) * 2) > b"));
+}
+
+#[test]
+fn evaluate() {
+    assert!(eval(": t >= 3 && (w < 0.1 || !leaf)"));
+    assert!(!eval(": t >= 3 && w < 0.1 || leaf"));
+    assert!(eval(": t == 4 && t != 3 && t <= 4"));
+    assert!(!eval(": !(t > 3)"));
+    assert!(eval(": t"));
+    assert!(eval(": t > 5 || w * 10 >= 2"));
+}
+
+#[test]
+fn display() {
+    for input in [
+        "t >= 3 && (w < 0.1 || !leaf)",
+        "a || b && c",
+        "(a || b) && c",
+        "a && (b && c)",
+        "!(a == 1) || !b",
+        "a - 1 != (b + c) * 2",
+    ] {
+        let condition = parse(&format!(": {input}"));
+        assert_eq!(condition.to_string(), input);
+        assert_eq!(parse(&format!(": {condition}")), condition);
+    }
+}
+
+#[test]
+fn probability() {
+    let (_, rule) = parse_rule("A : 0.5 -> B").unwrap();
+    assert_eq!(rule.condition, None);
+    assert_eq!(rule.probability, 0.5);
+
+    let (_, rule) = parse_rule("A(x) : x >= 1 && x != 2 : 0.5 -> B").unwrap();
+    assert_eq!(
+        rule.condition,
+        Some(and(
+            cmp(var("x"), Conditional::GreaterOrEqual, num(1.0)),
+            cmp(var("x"), Conditional::NotEqual, num(2.0)),
+        ))
+    );
+    assert_eq!(rule.probability, 0.5);
+}
+
+#[test]
+fn sample() {
+    let lsys = LSystemBuilder::new_str("A(0)")
+        .unwrap()
+        .rule_str("A(t) : t < 2 || t >= 4 && !(t == 5) -> A(t+1)")
+        .unwrap()
+        .rule_str("A(t) : t >= 2 && t < 4 -> A(t+2)")
+        .unwrap()
+        .build();
+    let params = |generation| lsys.sample(generation).get(0).unwrap().params.clone();
+    assert_eq!(params(3), vec![num(4.0)]);
+    assert_eq!(params(4), vec![num(5.0)]);
+    assert_eq!(params(5), vec![num(5.0)]);
+}
@@ -1,10 +1,25 @@
-use hyperion::{LSystemBuilder, Rule, State};
+use hyperion::{LSystemBuilder, Role, Rule, State, Unsupported};
 use pretty_assertions::assert_eq;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum Contextual {
     A,
     B,
+    C,
+    D,
+    E,
+    X,
+    Turn,
+    Push,
+    Pop,
+}
+
+fn role(token: &Contextual) -> Role {
+    match token {
+        Contextual::Push => Role::Push,
+        Contextual::Pop => Role::Pop,
+        _ => Role::Symbol,
+    }
 }
 
 #[test]
@@ -19,3 +34,72 @@ fn generations() {
     assert_eq!(lsys.sample(2), State::new([B, A, A, B, A, B]));
     assert_eq!(lsys.sample(3), State::new([B, A, A, B, A, B, A, A, B, B]));
 }
+
+#[test]
+fn multiple_modules() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([A, B, C, D, E, C, D, E])
+        .rule(Rule::new(C, [X]).with_previous([A, B]).with_next([D, E]))
+        .build();
+    assert_eq!(lsys.sample(1), State::new([A, B, X, D, E, C, D, E]));
+}
+
+#[test]
+fn right_context() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([A, B, A, C])
+        .rule(Rule::new(A, [X]).with_next([C]))
+        .build();
+    assert_eq!(lsys.sample(1), State::new([A, B, X, C]));
+}
+
+#[test]
+fn branching_left_context() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([A, Push, B, Pop, C, Push, Turn, D, Pop])
+        .rule(Rule::new(C, [X]).with_previous([A]))
+        .rule(Rule::new(D, [X]).with_previous([A, C]))
+        .rule(Rule::new(B, [X]).with_previous([A]))
+        .roles(role)
+        .ignore([Turn])
+        .build();
+    assert_eq!(
+        lsys.sample(1),
+        State::new([A, Push, X, Pop, X, Push, Turn, X, Pop])
+    );
+}
+
+#[test]
+fn branching_right_context() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([A, Push, B, Pop, Push, Turn, C, Pop, D, E])
+        .rule(Rule::new(A, [X]).with_next([C]))
+        .rule(Rule::new(B, [X]).with_next([D]))
+        .rule(Rule::new(D, [X]).with_next([E]))
+        .roles(role)
+        .ignore([Turn])
+        .build();
+    assert_eq!(
+        lsys.sample(1),
+        State::new([X, Push, B, Pop, Push, Turn, C, Pop, X, E])
+    );
+}
+
+#[test]
+fn brackets_without_roles() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([A, Push, B, Pop, C])
+        .rule(Rule::new(C, [X]).with_previous([A]))
+        .rule(Rule::new(B, [X]).with_previous([Push]))
+        .build();
+    assert_eq!(lsys.sample(1), State::new([A, Push, X, Pop, C]));
+}
+
+#[test]
+fn expand_iter_refuses_context() {
+    use Contextual::*;
+    let lsys = LSystemBuilder::new([B, A])
+        .rule(Rule::new(A, [A, A, B]).with_previous(Some(B)))
+        .build();
+    assert!(matches!(lsys.expand_iter(2), Err(Unsupported::Context)));
+}
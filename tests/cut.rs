@@ -0,0 +1,53 @@
+use hyperion::{LSystemBuilder, Role, Rule, State};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pruned {
+    F,
+    Leaf,
+    Old,
+    Cut,
+    Push,
+    Pop,
+}
+
+fn role(token: &Pruned) -> Role {
+    match token {
+        Pruned::Push => Role::Push,
+        Pruned::Pop => Role::Pop,
+        Pruned::Cut => Role::Cut,
+        _ => Role::Symbol,
+    }
+}
+
+#[test]
+fn cut_branch() {
+    use Pruned::*;
+    let lsys = LSystemBuilder::new([F, Push, Old, F, Push, Leaf, Pop, F, Pop, F, Push, Leaf, Pop])
+        .rule(Rule::new(Old, [Cut]))
+        .roles(role)
+        .build();
+    assert_eq!(
+        lsys.sample(1),
+        State::new([F, Push, Pop, F, Push, Leaf, Pop])
+    );
+}
+
+#[test]
+fn cut_top_level() {
+    use Pruned::*;
+    let lsys = LSystemBuilder::new([F, Old, F, Push, Leaf, Pop, F])
+        .rule(Rule::new(Old, [Cut]))
+        .roles(role)
+        .build();
+    assert_eq!(lsys.sample(1), State::new([F]));
+}
+
+#[test]
+fn cut_without_role() {
+    use Pruned::*;
+    let lsys = LSystemBuilder::new([F, Old, F])
+        .rule(Rule::new(Old, [Cut]))
+        .build();
+    assert_eq!(lsys.sample(1), State::new([F, Cut, F]));
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+
+use hyperion::{LSystemBuilder, Module, Rule, Value};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algae {
+    A,
+    B,
+}
+
+impl fmt::Display for Algae {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[test]
+fn derivation_tree() {
+    use Algae::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    assert_eq!(
+        lsys.derivation_dot(2).unwrap(),
+        r#"digraph derivation {
+    node [shape=plaintext];
+    g0_0 [label="A"];
+    g1_0 [label="A"];
+    g1_1 [label="B"];
+    g0_0 -> g1_0 [label="0: A -> AB"];
+    g0_0 -> g1_1 [label="0: A -> AB"];
+    g2_0 [label="A"];
+    g2_1 [label="B"];
+    g2_2 [label="A"];
+    g1_0 -> g2_0 [label="0: A -> AB"];
+    g1_0 -> g2_1 [label="0: A -> AB"];
+    g1_1 -> g2_2 [label="1: B -> A"];
+}
+"#
+    );
+}
+
+#[test]
+fn unchanged_modules_and_parameters() {
+    use Algae::*;
+    let lsys = LSystemBuilder::new([Module::new(A).params(vec![Value::Num(1.0)]), Module::new(B)])
+        .rule(Rule::new(A, [B]))
+        .build();
+    assert_eq!(
+        lsys.derivation_dot(1).unwrap(),
+        r#"digraph derivation {
+    node [shape=plaintext];
+    g0_0 [label="A(1)"];
+    g0_1 [label="B"];
+    g1_0 [label="B"];
+    g1_1 [label="B"];
+    g0_0 -> g1_0 [label="0: A -> B"];
+    g0_1 -> g1_1 [style=dashed];
+}
+"#
+    );
+}
@@ -0,0 +1,78 @@
+use hyperion::{
+    Condition, Conditional, Environment, LSystemBuilder, Module, Parameters,
+    Role, Rule, SampleOptions, State, Value,
+};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Open {
+    A,
+    B,
+    Query,
+}
+
+/// Answers each query with the number of `B` modules that come before it.
+struct Counter;
+
+impl Environment<Open> for Counter {
+    fn query(&mut self, state: &State<Open>, index: usize) -> Option<Parameters> {
+        let count = state.iter().take(index).filter(|x| x.token == Open::B).count();
+        Some(vec![Value::Num(count as f32)])
+    }
+}
+
+#[test]
+fn query_modules() {
+    use Open::*;
+    let lsys = LSystemBuilder::new([Module::new(A), Module::new(Query).params(vec![Value::Num(10.0)])])
+        .rule(
+            Rule::new(A, [B, A])
+                .with_next([Module::new(Query).params(vec![Value::Var("n".into())])])
+                .with_condition(Some(Condition::Compare(
+                    Value::Var("n".into()),
+                    Conditional::LessThan,
+                    Value::Num(3.0),
+                ))),
+        )
+        .roles(|x| match x {
+            Query => Role::Query,
+            _ => Role::Symbol,
+        })
+        .build();
+
+    let query = |n: f32| Module::new(Query).params(vec![Value::Num(n)]);
+    let sample = |generation| {
+        let options = SampleOptions {
+            environment: Some(&mut Counter),
+            ..Default::default()
+        };
+        lsys.sample_with(generation, options)
+    };
+    assert_eq!(sample(0), Ok(State::new([Module::new(A), query(0.0)])));
+    assert_eq!(
+        sample(5),
+        Ok(State::new([Module::new(B), Module::new(B), Module::new(B), Module::new(A), query(3.0)]))
+    );
+    assert_eq!(
+        lsys.sample(5),
+        State::new([Module::new(A), query(10.0)])
+    );
+}
+
+#[test]
+fn query_step() {
+    use Open::*;
+    let lsys = LSystemBuilder::new([Module::new(B), Module::new(Query)])
+        .roles(|x| match x {
+            Query => Role::Query,
+            _ => Role::Symbol,
+        })
+        .build();
+
+    let mut state = lsys.sample(0);
+    lsys.query(&mut state, &mut Counter);
+    assert_eq!(
+        state,
+        State::new([Module::new(B), Module::new(Query).params(vec![Value::Num(1.0)])])
+    );
+}
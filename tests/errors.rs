@@ -0,0 +1,171 @@
+use std::fmt;
+
+use hyperion::{
+    Condition, Conditional, Environment, EvalErrorKind, LSystem, LSystemBuilder, Module, Operator,
+    Parameters, Provenance, Rule, SampleError, SampleOptions, State, Value, Variables,
+};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Errors {
+    A,
+    B,
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// An environment that never answers.
+struct Silent;
+
+impl Environment<Errors> for Silent {
+    fn query(&mut self, _: &State<Errors>, _: usize) -> Option<Parameters> {
+        None
+    }
+}
+
+fn mul(a: Value, b: Value) -> Value {
+    Value::Expr(Box::new(a), Operator::Mul, Box::new(b))
+}
+
+/// A rule multiplying its parameter by the undefined variable `b`.
+fn broken() -> Rule<Errors> {
+    Rule::new(
+        Module::new(Errors::A).params(vec![Value::Var("l".into())]),
+        [Module::new(Errors::A).params(vec![mul(Value::Var("l".into()), Value::Var("b".into()))])],
+    )
+}
+
+fn assert_broken<E: Into<SampleError>>(err: E, generation: usize) {
+    let SampleError::Eval(err) = err.into() else {
+        panic!("expected an evaluation error");
+    };
+    assert_eq!(err.kind, EvalErrorKind::NonNumeric(Operator::Mul, Value::Var("b".into())));
+    assert_eq!(err.generation, Some(generation));
+}
+
+#[test]
+fn non_numeric_parameter() {
+    use Errors::*;
+    let lsys = LSystemBuilder::new([Module::new(B), Module::new(A).params(vec![Value::Num(1.0)])])
+        .rule(Rule::new(
+            Module::new(A).params(vec![Value::Var("l".into())]),
+            [Module::new(A).params(vec![mul(Value::Var("l".into()), Value::Var("b".into()))])],
+        ))
+        .build();
+
+    let err = lsys.try_sample(2).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::NonNumeric(Operator::Mul, Value::Var("b".into())));
+    assert_eq!(err.expr.as_deref(), Some("l * b"));
+    assert_eq!(err.rule, Some(0));
+    assert_eq!(err.module, Some(1));
+    assert_eq!(err.generation, Some(1));
+    assert_eq!(
+        err.to_string(),
+        "attempted to apply `*` to non numeric value `b` in `l * b` of rule 0 rewriting module 1 in generation 1"
+    );
+}
+
+#[test]
+#[should_panic(expected = "attempted to apply `*` to non numeric value `b`")]
+fn sample_panics() {
+    use Errors::*;
+    let lsys = LSystemBuilder::new([Module::new(A).params(vec![Value::Num(1.0)])])
+        .rule(Rule::new(
+            Module::new(A).params(vec![Value::Var("l".into())]),
+            [Module::new(A).params(vec![mul(Value::Var("l".into()), Value::Var("b".into()))])],
+        ))
+        .build();
+    lsys.sample(1);
+}
+
+#[test]
+fn invalid_condition() {
+    use Errors::*;
+    let lsys = LSystemBuilder::new([A, A])
+        .rule(Rule::new(B, [A]))
+        .rule(Rule::new(A, [B]).with_condition(Some(Condition::Compare(
+            Value::Var("T".into()),
+            Conditional::GreaterThan,
+            Value::Var("y".into()),
+        ))))
+        .build();
+
+    let err = lsys.try_sample(1).unwrap_err();
+    assert_eq!(
+        err.kind,
+        EvalErrorKind::NonNumericComparison(Conditional::GreaterThan, Value::Var("y".into()))
+    );
+    assert_eq!(err.expr.as_deref(), Some("T > y"));
+    assert_eq!(err.rule, Some(1));
+    assert_eq!(err.module, Some(0));
+}
+
+#[test]
+fn evaluate() {
+    let params = Variables::from([("x".to_string(), Value::Num(2.0))]);
+    let variables = Variables::new();
+
+    let expr = Value::Expr(Box::new(Value::Var("x".into())), Operator::Exponent, Box::new(Value::Num(3.0)));
+    assert_eq!(expr.try_evaluate(&params, &variables), Ok(Value::Num(8.0)));
+
+    let expr = Value::Expr(Box::new(Value::Var("y".into())), Operator::Exponent, Box::new(Value::Num(3.0)));
+    assert_eq!(
+        expr.try_evaluate(&params, &variables).unwrap_err().kind,
+        EvalErrorKind::NonNumeric(Operator::Exponent, Value::Var("y".into()))
+    );
+
+    let nested = Condition::And(
+        Box::new(Condition::Compare(Value::Var("x".into()), Conditional::GreaterThan, Value::Num(1.0))),
+        Box::new(Condition::Value(Value::Var("leaf".into()))),
+    );
+    let err = nested.evaluate(&params, &variables).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::NonNumericCondition(Value::Var("leaf".into())));
+    assert_eq!(err.expr.as_deref(), Some("leaf"));
+}
+
+#[test]
+fn fallible_paths() {
+    use Errors::*;
+    let lsys: LSystem<Errors> = LSystemBuilder::new([Module::new(A).params(vec![Value::Num(1.0)])])
+        .rule(broken())
+        .build();
+    let axiom = lsys.sample(0);
+
+    assert_broken(lsys.step(&axiom, 1).unwrap_err(), 1);
+    let options = SampleOptions {
+        environment: Some(&mut Silent),
+        ..Default::default()
+    };
+    assert_broken(lsys.sample_with(1, options).unwrap_err(), 1);
+    let mut provenance = vec![Provenance::default()];
+    let options = SampleOptions {
+        provenance: Some(&mut provenance),
+        ..Default::default()
+    };
+    assert_broken(lsys.step_with(&axiom, 1, options).unwrap_err(), 1);
+    assert_broken(lsys.derivation_dot(1).unwrap_err(), 1);
+    assert!(matches!(lsys.generations().nth(1), Some(Err(_))));
+    assert!(lsys.generations().nth(2).is_none());
+
+    let mut expand = lsys.expand_iter(1).unwrap();
+    assert_broken(expand.next().unwrap().unwrap_err(), 1);
+    assert!(expand.next().is_none());
+
+    assert_broken(lsys.slice(1, 0..1).unwrap_err(), 1);
+    assert!(matches!(lsys.module_at(1, 0), Err(SampleError::Eval(_))));
+}
+
+#[test]
+fn fallible_interpretation() {
+    use Errors::*;
+    let lsys = LSystemBuilder::new([Module::new(A).params(vec![Value::Num(1.0)])])
+        .homomorphism(broken())
+        .build();
+
+    assert_broken(lsys.interpret(&lsys.sample(2), 2).unwrap_err(), 2);
+    assert_broken(lsys.sample_interpreted(2).unwrap_err(), 2);
+}
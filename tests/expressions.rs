@@ -0,0 +1,235 @@
+#![cfg(feature = "grammar")]
+
+use std::collections::HashMap;
+
+use hyperion::{
+    grammar::parser::parse_expr, EvalErrorKind, Function, LSystemBuilder, Operator, SampleError,
+    Unsupported, Value,
+};
+use pretty_assertions::assert_eq;
+
+fn num(x: f32) -> Value {
+    Value::Num(x)
+}
+
+fn var(x: &str) -> Value {
+    Value::Var(x.to_string())
+}
+
+fn expr(a: Value, op: Operator, b: Value) -> Value {
+    Value::Expr(Box::new(a), op, Box::new(b))
+}
+
+fn neg(x: Value) -> Value {
+    Value::Neg(Box::new(x))
+}
+
+fn call<const N: usize>(func: Function, args: [Value; N]) -> Value {
+    Value::Call(func, args.into())
+}
+
+fn eval(input: &str) -> f32 {
+    parse(input)
+        .evaluate(&HashMap::new(), &HashMap::from([("a".to_string(), num(0.5)), ("b".to_string(), num(3.0))]))
+        .to_float()
+        .unwrap()
+}
+
+fn parse(input: &str) -> Value {
+    let (rest, value) = parse_expr(input).unwrap();
+    assert_eq!(rest, "", "unparsed input after `{input}`");
+    value
+}
+
+#[test]
+fn precedence() {
+    use Operator::*;
+    assert_eq!(parse("l*b+1"), expr(expr(var("l"), Mul, var("b")), Add, num(1.0)));
+    assert_eq!(parse("1+l*b"), expr(num(1.0), Add, expr(var("l"), Mul, var("b"))));
+    assert_eq!(
+        parse("a + b * c ^ 2"),
+        expr(var("a"), Add, expr(var("b"), Mul, expr(var("c"), Exponent, num(2.0))))
+    );
+}
+
+#[test]
+fn associativity() {
+    use Operator::*;
+    assert_eq!(parse("a-b-c"), expr(expr(var("a"), Sub, var("b")), Sub, var("c")));
+    assert_eq!(parse("a/b*c"), expr(expr(var("a"), Div, var("b")), Mul, var("c")));
+    assert_eq!(
+        parse("a^b^c"),
+        expr(var("a"), Exponent, expr(var("b"), Exponent, var("c")))
+    );
+}
+
+#[test]
+fn parentheses() {
+    use Operator::*;
+    assert_eq!(parse("(n+1)/2"), expr(expr(var("n"), Add, num(1.0)), Div, num(2.0)));
+    assert_eq!(parse("( ( n ) )"), var("n"));
+    assert_eq!(
+        parse("a*(b-(c+d))"),
+        expr(var("a"), Mul, expr(var("b"), Sub, expr(var("c"), Add, var("d"))))
+    );
+}
+
+#[test]
+fn unary_minus() {
+    use Operator::*;
+    assert_eq!(parse("-x"), neg(var("x")));
+    assert_eq!(parse("-3"), num(-3.0));
+    assert_eq!(parse("-x^2"), neg(expr(var("x"), Exponent, num(2.0))));
+    assert_eq!(parse("-2*x"), expr(num(-2.0), Mul, var("x")));
+    assert_eq!(parse("a - -b"), expr(var("a"), Sub, neg(var("b"))));
+    assert_eq!(parse("2^-x"), expr(num(2.0), Exponent, neg(var("x"))));
+    assert_eq!(parse("-(a+b)"), neg(expr(var("a"), Add, var("b"))));
+}
+
+#[test]
+fn round_trip() {
+    use Operator::*;
+    let trees = [
+        expr(expr(var("n"), Add, num(1.0)), Div, num(2.0)),
+        expr(var("a"), Sub, expr(var("b"), Sub, var("c"))),
+        expr(expr(var("a"), Exponent, var("b")), Exponent, var("c")),
+        expr(neg(var("x")), Exponent, num(2.0)),
+        expr(num(-2.0), Exponent, num(2.0)),
+        neg(expr(var("x"), Mul, var("y"))),
+        expr(var("l"), Mul, expr(var("b"), Add, num(1.0))),
+        expr(expr(var("a"), Rem, var("b")), Mul, var("c")),
+        expr(var("a"), Rem, expr(var("b"), Mul, var("c"))),
+        call(Function::Clamp, [neg(var("x")), num(0.0), expr(var("a"), Add, num(1.0))]),
+    ];
+    for tree in trees {
+        assert_eq!(parse(&tree.to_string()), tree, "round trip of `{tree}`");
+    }
+}
+
+#[test]
+fn rule_expressions() {
+    let lsys = LSystemBuilder::new_str("A(2,3)")
+        .unwrap()
+        .rule_str("A(l,b) : l*b+1 > 6 -> A(-l, (l+b)/2) F(l*sin(b))")
+        .unwrap()
+        .build();
+    let state = lsys.sample(1);
+    assert_eq!(state.get(0).unwrap().params, vec![num(-2.0), num(2.5)]);
+    assert_eq!(state.get(1).unwrap().params, vec![num(2.0 * 3f32.sin())]);
+}
+
+#[test]
+fn functions() {
+    use Operator::*;
+    assert_eq!(parse("l*sin(a)"), expr(var("l"), Mul, call(Function::Sin, [var("a")])));
+    assert_eq!(
+        parse("max(a, b+1)"),
+        call(Function::Max, [var("a"), expr(var("b"), Add, num(1.0))])
+    );
+    assert_eq!(parse("-abs(x)"), neg(call(Function::Abs, [var("x")])));
+    assert!(parse_expr("min(a)").map_or(true, |(rest, _)| !rest.is_empty()));
+
+    assert_eq!(eval("sin(a)"), 0.5f32.sin());
+    assert_eq!(eval("cos(a) + tan(a)"), 0.5f32.cos() + 0.5f32.tan());
+    assert_eq!(eval("sqrt(b*3)"), 3.0);
+    assert_eq!(eval("floor(b/2) + ceil(b/2)"), 3.0);
+    assert_eq!(eval("min(a, b) * max(a, b)"), 1.5);
+    assert_eq!(eval("clamp(b, 0, 1)"), 1.0);
+    assert_eq!(eval("log(exp(b))"), 3.0);
+}
+
+#[test]
+fn modulo() {
+    use Operator::*;
+    assert_eq!(parse("a+b%2"), expr(var("a"), Add, expr(var("b"), Rem, num(2.0))));
+    assert_eq!(eval("b % 2"), 1.0);
+    assert_eq!(eval("7 % b * 2"), 2.0);
+}
+
+#[test]
+fn function_errors() {
+    let args = HashMap::new();
+    let err = call(Function::Min, [num(1.0)]).try_evaluate(&args, &args).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::Arity(Function::Min, 1));
+    assert_eq!(err.to_string(), "`min` takes 2 arguments but 1 were given in `min(1)`");
+
+    let err = call(Function::Sqrt, [var("x")]).try_evaluate(&args, &args).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::NonNumericArgument(Function::Sqrt, var("x")));
+}
+
+fn floats(lsys: &hyperion::LSystem<hyperion::grammar::Token>) -> Vec<f32> {
+    lsys.sample(1)
+        .iter()
+        .map(|x| x.params[0].to_float().unwrap())
+        .collect()
+}
+
+#[test]
+fn random_functions() {
+    let axiom = "A".repeat(2000);
+    let uniform = LSystemBuilder::new_str(&axiom)
+        .unwrap()
+        .rule_str("A -> F(uniform(-5, 5))")
+        .unwrap()
+        .seed(7)
+        .build();
+    let values = floats(&uniform);
+    assert_eq!(values, floats(&uniform));
+    assert!(values.iter().all(|x| (-5.0..5.0).contains(x)));
+
+    let normal = LSystemBuilder::new_str(&axiom)
+        .unwrap()
+        .rule_str("A -> F(normal(10, 2))")
+        .unwrap()
+        .seed(7)
+        .build();
+    let values = floats(&normal);
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    assert!((mean - 10.0).abs() < 0.2, "mean was {mean}");
+    assert!((variance.sqrt() - 2.0).abs() < 0.2, "deviation was {}", variance.sqrt());
+}
+
+#[test]
+fn random_slice() {
+    let lsys = LSystemBuilder::new_str("A(1)")
+        .unwrap()
+        .rule_str("A(x) -> F(uniform(0, 1))A(x)")
+        .unwrap()
+        .seed(9)
+        .build();
+    assert_eq!(lsys.slice(3, 0..4), Err(SampleError::Unsupported(Unsupported::Stochastic)));
+    assert_eq!(lsys.module_at(3, 0), Err(SampleError::Unsupported(Unsupported::Stochastic)));
+    assert!(!parse("x * sin(x)").is_random());
+    assert!(parse("1 + min(x, uniform(0, 1))").is_random());
+}
+
+#[test]
+fn random_conditions() {
+    let lsys = LSystemBuilder::new_str(&"A".repeat(100))
+        .unwrap()
+        .rule_str("A : rand() < 0.5 -> B")
+        .unwrap()
+        .seed(3)
+        .build();
+    let state = lsys.sample(1);
+    assert_eq!(state, lsys.sample(1));
+    let rewritten = state.iter().filter(|x| x.to_string() == "B").count();
+    assert!((25..75).contains(&rewritten), "rewrote {rewritten} modules");
+}
+
+#[test]
+fn multi_character_names() {
+    use Operator::*;
+    assert_eq!(parse("len*decay"), expr(var("len"), Mul, var("decay")));
+    assert_eq!(parse("width_2 + inf"), expr(var("width_2"), Add, var("inf")));
+    assert_eq!(parse_expr("sine(x)"), Ok(("(x)", var("sine"))));
+
+    let lsys = LSystemBuilder::new_str("A(2,1)")
+        .unwrap()
+        .rule_str("A(len, width) : len > width -> F(len*decay, width)")
+        .unwrap()
+        .variable("decay", num(0.5))
+        .build();
+    assert_eq!(lsys.sample(1).get(0).unwrap().params, vec![num(1.0), num(1.0)]);
+}
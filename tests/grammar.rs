@@ -1,4 +1,6 @@
-use hyperion::{grammar::Token, Condition, Conditional, ConditionalValue, LSystemBuilder, Module, Operator, Rule, State, Value};
+#![cfg(feature = "grammar")]
+
+use hyperion::{grammar::{parser::parse_rule, Token}, Condition, Conditional, LSystemBuilder, Module, Operator, Rule, State, Value};
 use pretty_assertions::assert_eq;
 
 #[test]
@@ -10,24 +12,24 @@ fn parse_sympodial() {
         .build();
 
     let axiom_mod = Module::new(Token::External('A')).params(vec![Value::Num(1.0), Value::Num(0.25)]);
-    let rule_mod = Module::new(Token::External('A')).params(vec![Value::Var('l'), Value::Var('w')]);
+    let rule_mod = Module::new(Token::External('A')).params(vec![Value::Var("l".into()), Value::Var("w".into())]);
     let rule_state = State::new([
-        Module::new(Token::F).params(vec![Value::Var('l'), Value::Var('w')]),
+        Module::new(Token::F).params(vec![Value::Var("l".into()), Value::Var("w".into())]),
         Module::new(Token::Push),
         Module::new(Token::External('W')),
-        Module::new(Token::Up).params(vec![Value::Var('c')]),
+        Module::new(Token::Up).params(vec![Value::Var("c".into())]),
         Module::new(Token::External('B')).params(vec![
-            Value::Expr(Box::new(Value::Var('l')), Operator::Mul, Box::new(Value::Var('b'))),
-            Value::Expr(Box::new(Value::Var('w')), Operator::Mul, Box::new(Value::Var('h')))
+            Value::Expr(Box::new(Value::Var("l".into())), Operator::Mul, Box::new(Value::Var("b".into()))),
+            Value::Expr(Box::new(Value::Var("w".into())), Operator::Mul, Box::new(Value::Var("h".into())))
         ]),
         Module::new(Token::Pop),
         Module::new(Token::Roll),
         Module::new(Token::Roll).params(vec![Value::Num(180.0)]),
         Module::new(Token::Push),
-        Module::new(Token::Up).params(vec![Value::Var('d')]),
+        Module::new(Token::Up).params(vec![Value::Var("d".into())]),
         Module::new(Token::External('B')).params(vec![
-            Value::Expr(Box::new(Value::Var('l')), Operator::Mul, Box::new(Value::Var('e'))),
-            Value::Expr(Box::new(Value::Var('w')), Operator::Mul, Box::new(Value::Var('h')))
+            Value::Expr(Box::new(Value::Var("l".into())), Operator::Mul, Box::new(Value::Var("e".into()))),
+            Value::Expr(Box::new(Value::Var("w".into())), Operator::Mul, Box::new(Value::Var("h".into())))
         ]),
     ]);
     let coded = LSystemBuilder::new(State::new([axiom_mod]))
@@ -60,19 +62,19 @@ fn parse_rose_leaf() {
         Module::new(Token::EndPolygon),
         Module::new(Token::Pop),
     ]);
-    let rule_mod = Module::new(Token::External('B')).params(vec![Value::Var('i')]);
+    let rule_mod = Module::new(Token::External('B')).params(vec![Value::Var("i".into())]);
     let rule_state = State::new([
-        Module::new(Token::F).params(vec![Value::Var('d'), Value::Var('e')]),
+        Module::new(Token::F).params(vec![Value::Var("d".into()), Value::Var("e".into())]),
         Module::new(Token::External('B')).params(vec![
-            Value::Expr(Box::new(Value::Var('i')), Operator::Sub, Box::new(Value::Num(1.0))),
+            Value::Expr(Box::new(Value::Var("i".into())), Operator::Sub, Box::new(Value::Num(1.0))),
         ]),
     ]);
     let coded = LSystemBuilder::new(axiom_mod)
-        .rule(Rule::new(rule_mod, rule_state).with_condition(Some(Condition {
-            a: ConditionalValue::Value(Value::Var('i')),
-            cond: Conditional::GreaterThan,
-            b: ConditionalValue::Value(Value::Num(0.0))
-        })))
+        .rule(Rule::new(rule_mod, rule_state).with_condition(Some(Condition::Compare(
+            Value::Var("i".into()),
+            Conditional::GreaterThan,
+            Value::Num(0.0),
+        ))))
         .build();
     
     assert_eq!(coded.axiom, parsed.axiom);
@@ -88,14 +90,14 @@ fn parse_phylotaxis() {
         .build();
 
     let axiom_mod = Module::new(Token::External('A')).params(vec![Value::Num(0.0)]);
-    let rule_mod = Module::new(Token::External('A')).params(vec![Value::Var('n')]);
+    let rule_mod = Module::new(Token::External('A')).params(vec![Value::Var("n".into())]);
     let rule_state = State::new([
-        Module::new(Token::Left).params(vec![Value::Var('a')]),
+        Module::new(Token::Left).params(vec![Value::Var("a".into())]),
         Module::new(Token::Push),
-        Module::new(Token::External('f')).params(vec![Value::Expr(Box::new(Value::Var('n')), Operator::Exponent, Box::new(Value::Num(0.5)))]),
+        Module::new(Token::External('f')).params(vec![Value::Expr(Box::new(Value::Var("n".into())), Operator::Exponent, Box::new(Value::Num(0.5)))]),
         Module::new(Token::External('D')),
         Module::new(Token::Pop),
-        Module::new(Token::External('A')).params(vec![Value::Expr(Box::new(Value::Var('n')), Operator::Add, Box::new(Value::Num(1.0)))]),
+        Module::new(Token::External('A')).params(vec![Value::Expr(Box::new(Value::Var("n".into())), Operator::Add, Box::new(Value::Num(1.0)))]),
     ]);
     let coded = LSystemBuilder::new(State::new([axiom_mod]))
         .rule(Rule::new(rule_mod, rule_state))
@@ -103,4 +105,134 @@ fn parse_phylotaxis() {
     
     assert_eq!(coded.axiom, parsed.axiom);
     assert_eq!(coded.rules, parsed.rules);
-}
\ No newline at end of file
+}
+#[test]
+fn parse_context() {
+    let parsed = LSystemBuilder::new_str("ABCDE")
+        .unwrap()
+        .rule_str("AB < C > DE -> X")
+        .unwrap()
+        .rule_str("A > B -> F")
+        .unwrap()
+        .rule_str("B(x) < C : x > 1 -> F")
+        .unwrap()
+        .build();
+
+    let external = |c| Module::new(Token::External(c));
+    let coded = LSystemBuilder::new(State::new([
+        external('A'),
+        external('B'),
+        external('C'),
+        external('D'),
+        external('E'),
+    ]))
+    .rule(
+        Rule::new(external('C'), [external('X')])
+            .with_previous([external('A'), external('B')])
+            .with_next([external('D'), external('E')]),
+    )
+    .rule(Rule::new(external('A'), [Token::F]).with_next([external('B')]))
+    .rule(
+        Rule::new(external('C'), [Token::F])
+            .with_previous([external('B').params(vec![Value::Var("x".into())])])
+            .with_condition(Some(Condition::Compare(
+                Value::Var("x".into()),
+                Conditional::GreaterThan,
+                Value::Num(1.0),
+            ))),
+    )
+    .build();
+
+    assert_eq!(coded.axiom, parsed.axiom);
+    assert_eq!(coded.rules, parsed.rules);
+}
+
+#[test]
+fn parse_ignore() {
+    let parsed = LSystemBuilder::new_str("A[+B]-C")
+        .unwrap()
+        .ignore_str("+-")
+        .unwrap()
+        .rule_str("A < C -> F")
+        .unwrap()
+        .build();
+
+    assert_eq!(parsed.ignore, vec![Token::Left, Token::Right]);
+    assert_eq!(parsed.sample(1), LSystemBuilder::new_str("A[+B]-F").unwrap().build().sample(0));
+}
+
+#[test]
+fn context_parameters() {
+    let parsed = LSystemBuilder::new_str("A(3)B(1)C(2)A(0)B(1)C(2)")
+        .unwrap()
+        .rule_str("A(x) < B(y) > C(z) : x > y -> B(x+z)")
+        .unwrap()
+        .build();
+
+    let expected = LSystemBuilder::new_str("A(3)B(5)C(2)A(0)B(1)C(2)").unwrap().build();
+    assert_eq!(parsed.sample(1), expected.sample(0));
+}
+
+#[test]
+fn parse_query() {
+    let parsed = LSystemBuilder::new_str("A?E(0)")
+        .unwrap()
+        .rule_str("A > ?E(r) : r > 0 -> F")
+        .unwrap()
+        .build();
+
+    let query = |r| Module::new(Token::Query('E')).params(vec![r]);
+    let coded = LSystemBuilder::new(State::new([Module::new(Token::External('A')), query(Value::Num(0.0))]))
+        .rule(
+            Rule::new(Token::External('A'), [Token::F])
+                .with_next([query(Value::Var("r".into()))])
+                .with_condition(Some(Condition::Compare(
+                    Value::Var("r".into()),
+                    Conditional::GreaterThan,
+                    Value::Num(0.0),
+                ))),
+        )
+        .build();
+
+    assert_eq!(coded.axiom, parsed.axiom);
+    assert_eq!(coded.rules, parsed.rules);
+    assert_eq!(format!("{}", parsed.sample(0)), "A?E");
+}
+
+#[test]
+fn parse_homomorphism() {
+    let parsed = LSystemBuilder::new_str("A")
+        .unwrap()
+        .rule_str("A -> IA")
+        .unwrap()
+        .decomposition_str("I -> FL")
+        .unwrap()
+        .homomorphism_str("L -> [{.-F.+F.}]")
+        .unwrap()
+        .build();
+
+    assert_eq!(format!("{}", parsed.sample(2)), "FLFLA");
+    assert_eq!(format!("{}", parsed.sample_interpreted(2).unwrap()), "F[{.-F.+F.}]F[{.-F.+F.}]A");
+}
+
+#[test]
+fn parse_cut() {
+    let parsed = LSystemBuilder::new_str("F[L(3)F]F[L(0)F]")
+        .unwrap()
+        .rule_str("L(a) : a < 1 -> %")
+        .unwrap()
+        .build();
+
+    assert_eq!(format!("{}", parsed.sample(1)), "F[LF]F[]");
+}
+
+#[test]
+fn display_rule() {
+    for rule in [
+        "A -> F[+A]",
+        "B < A(x, y) > C : x > 1 && y != 0 : 0.5 -> A(x + 1, y)B",
+        "L(a) : a < 1 -> %",
+    ] {
+        assert_eq!(parse_rule(rule).unwrap().1.to_string(), rule);
+    }
+}
@@ -0,0 +1,69 @@
+use hyperion::{EvalErrorKind, LSystemBuilder, Rule, State};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Plant {
+    Apex,
+    Internode,
+    Node,
+    Leaf,
+    F,
+    Push,
+    Pop,
+}
+
+#[test]
+fn decomposition() {
+    use Plant::*;
+    let lsys = LSystemBuilder::new([Apex])
+        .rule(Rule::new(Apex, [Internode, Apex]))
+        .decomposition(Rule::new(Internode, [F, Node]))
+        .decomposition(Rule::new(Node, [Push, Leaf, Pop]))
+        .build();
+    assert_eq!(lsys.sample(0), State::new([Apex]));
+    assert_eq!(lsys.sample(1), State::new([F, Push, Leaf, Pop, Apex]));
+    assert_eq!(
+        lsys.sample(2),
+        State::new([F, Push, Leaf, Pop, F, Push, Leaf, Pop, Apex])
+    );
+}
+
+#[test]
+fn decomposed_axiom() {
+    use Plant::*;
+    let lsys = LSystemBuilder::new([Internode, Apex])
+        .rule(Rule::new(Apex, [Internode, Apex]))
+        .decomposition(Rule::new(Internode, [F, Node]))
+        .build();
+    assert_eq!(lsys.sample(0), State::new([F, Node, Apex]));
+    assert_eq!(lsys.sample(1), State::new([F, Node, F, Node, Apex]));
+    assert_eq!(lsys.generations().next(), Some(Ok(lsys.sample(0))));
+}
+
+#[test]
+fn endless_decomposition() {
+    use Plant::*;
+    let lsys = LSystemBuilder::new([Apex])
+        .rule(Rule::new(Apex, [Internode, Apex]))
+        .decomposition(Rule::new(Internode, [Node]))
+        .decomposition(Rule::new(Node, [Internode]))
+        .build();
+    let err = lsys.try_sample(1).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::MaxDepth(32));
+    assert_eq!(err.generation, Some(1));
+}
+
+#[test]
+fn homomorphism() {
+    use Plant::*;
+    let lsys = LSystemBuilder::new([Apex])
+        .rule(Rule::new(Apex, [Internode, Leaf, Apex]))
+        .homomorphism(Rule::new(Leaf, [Push, F, Pop]))
+        .build();
+    assert_eq!(lsys.sample(2), State::new([Internode, Leaf, Internode, Leaf, Apex]));
+    assert_eq!(
+        lsys.sample_interpreted(2),
+        Ok(State::new([Internode, Push, F, Pop, Internode, Push, F, Pop, Apex]))
+    );
+    assert_eq!(lsys.interpret(&lsys.sample(1), 1), Ok(State::new([Internode, Push, F, Pop, Apex])));
+}
@@ -17,3 +17,43 @@ fn generations() {
     assert_eq!(lsys.sample(0), State::new([F]));
     assert_eq!(lsys.sample(1), State::new(vec![F, L, F, R, F, R, F, L, F]));
 }
+
+#[test]
+fn expand_iter() {
+    use KochCurve::*;
+    let lsys = LSystemBuilder::new([F, R, R, F, R, R, F])
+        .rule(Rule::new(F, [F, L, F, R, F, R, F, L, F]))
+        .build();
+    let expanded = lsys.expand_iter(4).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(State::new(expanded), lsys.sample(4));
+    assert_eq!(lsys.expand_iter(0).unwrap().count(), 7);
+}
+
+#[test]
+fn module_at() {
+    use KochCurve::*;
+    let lsys = LSystemBuilder::new([F, R, R, F, R, R, F])
+        .rule(Rule::new(F, [F, L, F, R, F, R, F, L, F]))
+        .build();
+    let state = lsys.sample(3);
+    for (index, module) in state.iter().enumerate() {
+        assert_eq!(lsys.module_at(3, index), Ok(Some(module.clone())));
+    }
+    assert_eq!(lsys.module_at(3, state.len()), Ok(None));
+    assert_eq!(lsys.module_at(40, 9usize.pow(20) - 1), Ok(Some(F.into())));
+    assert_eq!(lsys.module_at(40, 9usize.pow(20)), Ok(Some(L.into())));
+}
+
+#[test]
+fn slice() {
+    use KochCurve::*;
+    let lsys = LSystemBuilder::new([F, R, R, F, R, R, F])
+        .rule(Rule::new(F, [F, L, F, R, F, R, F, L, F]))
+        .build();
+    let state = lsys.sample(4);
+    assert_eq!(
+        lsys.slice(4, 100..150),
+        Ok(State::new(state.iter().skip(100).take(50).cloned()))
+    );
+    assert_eq!(lsys.slice(4, 0..usize::MAX), Ok(state));
+}
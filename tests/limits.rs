@@ -0,0 +1,104 @@
+use std::{sync::atomic::AtomicBool, time::Duration};
+
+use hyperion::{
+    LSystem, Limit, LimitExceeded, LSystemBuilder, Rule, SampleError, SampleLimits, SampleOptions,
+    State,
+};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Explosive {
+    A,
+}
+
+fn doubling() -> hyperion::LSystem<Explosive> {
+    use Explosive::*;
+    LSystemBuilder::new([A]).rule(Rule::new(A, [A, A])).build()
+}
+
+fn sample(
+    lsys: &LSystem<Explosive>,
+    generation: usize,
+    limits: SampleLimits<'_>,
+) -> Result<State<Explosive>, SampleError> {
+    let options = SampleOptions {
+        limits,
+        ..Default::default()
+    };
+    lsys.sample_with(generation, options)
+}
+
+#[test]
+fn within_limits() {
+    let lsys = doubling();
+    let limits = SampleLimits {
+        max_modules: Some(1024),
+        max_duration: Some(Duration::from_secs(60)),
+        ..Default::default()
+    };
+    assert_eq!(sample(&lsys, 10, limits), Ok(State::new([Explosive::A; 1024])));
+}
+
+#[test]
+fn max_modules() {
+    let lsys = doubling();
+    let limits = SampleLimits {
+        max_modules: Some(1000),
+        ..Default::default()
+    };
+    assert_eq!(
+        sample(&lsys, 40, limits),
+        Err(SampleError::Limit(LimitExceeded {
+            limit: Limit::Modules,
+            generation: 10,
+            modules: 1002,
+        }))
+    );
+}
+
+#[test]
+fn max_duration() {
+    let lsys = doubling();
+    let limits = SampleLimits {
+        max_duration: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    let err = sample(&lsys, 40, limits).unwrap_err();
+    assert!(matches!(err, SampleError::Limit(LimitExceeded { limit: Limit::Duration, .. })));
+}
+
+#[test]
+fn cancel() {
+    let lsys = doubling();
+    let cancel = AtomicBool::new(true);
+    let limits = SampleLimits {
+        cancel: Some(&cancel),
+        ..Default::default()
+    };
+    assert_eq!(
+        sample(&lsys, 40, limits),
+        Err(SampleError::Limit(LimitExceeded {
+            limit: Limit::Cancelled,
+            generation: 0,
+            modules: 1,
+        }))
+    );
+}
+
+#[test]
+fn decomposition_limits() {
+    use Explosive::*;
+    let lsys = LSystemBuilder::new([A]).decomposition(Rule::new(A, [A, A])).build();
+    let limits = SampleLimits {
+        max_modules: Some(1000),
+        ..Default::default()
+    };
+    assert_eq!(
+        sample(&lsys, 1, limits),
+        Err(SampleError::Limit(LimitExceeded {
+            limit: Limit::Modules,
+            generation: 0,
+            modules: 1002,
+        }))
+    );
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "parallel")]
+
+use hyperion::{EvalErrorKind, LSystemBuilder, Module, Operator, Rule, Value};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algea {
+    A,
+    B,
+}
+
+#[test]
+fn matches_serial() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    assert_eq!(lsys.par_sample(0), Ok(lsys.sample(0)));
+    assert_eq!(lsys.par_sample(5), Ok(lsys.sample(5)));
+    assert_eq!(lsys.par_sample(22), Ok(lsys.sample(22)));
+}
+
+#[test]
+fn seeded_thread_count() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([A; 64])
+        .rule(Rule::new(A, [A, B]).with_probability(0.5))
+        .rule(Rule::new(A, [B, A]).with_probability(0.5))
+        .rule(Rule::new(B, [A]))
+        .seed(5)
+        .build();
+
+    let sample = |threads| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| lsys.par_sample(12))
+    };
+    assert_eq!(sample(1), sample(4));
+}
+
+#[test]
+fn evaluation_errors() {
+    use Algea::*;
+    let lsys = LSystemBuilder::new([Module::new(A).params(vec![Value::Num(1.0)])])
+        .rule(Rule::new(
+            Module::new(A).params(vec![Value::Var("l".into())]),
+            [Module::new(A).params(vec![Value::Expr(
+                Box::new(Value::Var("l".into())),
+                Operator::Add,
+                Box::new(Value::Var("b".into())),
+            )])],
+        ))
+        .build();
+    let err = lsys.par_sample(2).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::NonNumeric(Operator::Add, Value::Var("b".into())));
+    assert_eq!(err.generation, Some(1));
+}
@@ -1,4 +1,6 @@
-use hyperion::{LSystemBuilder, Module, Operator, Rule, State, Value};
+use hyperion::{
+    Condition, Conditional, LSystemBuilder, Module, Operator, Rule, State, Value,
+};
 use pretty_assertions::assert_eq;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -9,6 +11,8 @@ pub enum Parametric {
     Roll,
     Push,
     Pop,
+    A,
+    C,
 }
 
 #[test]
@@ -20,22 +24,22 @@ fn generations() {
     let axiom = [Module::new(B).params(axiom_params)];
     let lsys = LSystemBuilder::new(axiom)
         .rule(Rule::new(
-            Module::new(B).params(vec![Var('l'), Var('w')]),
+            Module::new(B).params(vec![Var("l".into()), Var("w".into())]),
             [
-                Module::new(F).params(vec![Var('l'), Var('w')]),
+                Module::new(F).params(vec![Var("l".into()), Var("w".into())]),
                 Module::new(Push),
-                Module::new(Up).params(vec![Var('c')]),
+                Module::new(Up).params(vec![Var("c".into())]),
                 Module::new(B).params(vec![
-                    Expr(Box::new(Var('l')), Mul, Box::new(Var('b'))),
-                    Expr(Box::new(Var('w')), Mul, Box::new(Var('h'))),
+                    Expr(Box::new(Var("l".into())), Mul, Box::new(Var("b".into()))),
+                    Expr(Box::new(Var("w".into())), Mul, Box::new(Var("h".into()))),
                 ]),
                 Module::new(Roll),
                 Module::new(Roll).params(vec![Value::Num(180.0)]),
                 Module::new(Push),
-                Module::new(Up).params(vec![Value::Var('d')]),
+                Module::new(Up).params(vec![Value::Var("d".into())]),
                 Module::new(B).params(vec![
-                    Expr(Box::new(Var('l')), Mul, Box::new(Var('e'))),
-                    Expr(Box::new(Var('w')), Mul, Box::new(Var('h'))),
+                    Expr(Box::new(Var("l".into())), Mul, Box::new(Var("e".into()))),
+                    Expr(Box::new(Var("w".into())), Mul, Box::new(Var("h".into()))),
                 ]),
             ],
         ))
@@ -60,3 +64,66 @@ fn generations() {
         ])
     );
 }
+
+#[test]
+fn context_parameters() {
+    use Operator::*;
+    use Parametric::*;
+    use Value::*;
+    let lsys = LSystemBuilder::new([
+        Module::new(A).params(vec![Num(3.0)]),
+        Module::new(B).params(vec![Num(1.0)]),
+        Module::new(C).params(vec![Num(2.0)]),
+        Module::new(A).params(vec![Num(0.0)]),
+        Module::new(B).params(vec![Num(1.0)]),
+        Module::new(C).params(vec![Num(2.0)]),
+    ])
+    .rule(
+        Rule::new(
+            Module::new(B).params(vec![Var("y".into())]),
+            [Module::new(B).params(vec![Expr(Box::new(Var("x".into())), Add, Box::new(Var("z".into())))])],
+        )
+        .with_previous([Module::new(A).params(vec![Var("x".into())])])
+        .with_next([Module::new(C).params(vec![Var("z".into())])])
+        .with_condition(Some(Condition::Compare(
+            Var("x".into()),
+            Conditional::GreaterThan,
+            Var("y".into()),
+        ))),
+    )
+    .build();
+    assert_eq!(
+        lsys.sample(1),
+        State::new([
+            Module::new(A).params(vec![Num(3.0)]),
+            Module::new(B).params(vec![Num(5.0)]),
+            Module::new(C).params(vec![Num(2.0)]),
+            Module::new(A).params(vec![Num(0.0)]),
+            Module::new(B).params(vec![Num(1.0)]),
+            Module::new(C).params(vec![Num(2.0)]),
+        ])
+    );
+}
+
+#[test]
+fn slice() {
+    use Operator::*;
+    use Parametric::*;
+    use Value::*;
+    let lsys = LSystemBuilder::new([Module::new(A).params(vec![Num(0.0)])])
+        .rule(Rule::new(
+            Module::new(A).params(vec![Var("x".into())]),
+            [
+                Module::new(B).params(vec![Var("x".into())]),
+                Module::new(A).params(vec![Expr(Box::new(Var("x".into())), Add, Box::new(Var("T".into())))]),
+                Module::new(C).params(vec![Var("x".into())]),
+            ],
+        ))
+        .build();
+    let state = lsys.sample(6);
+    assert_eq!(
+        lsys.slice(6, 3..9),
+        Ok(State::new(state.iter().skip(3).take(6).cloned()))
+    );
+    assert_eq!(lsys.module_at(6, 6), Ok(Some(Module::new(A).params(vec![Num(21.0)]))));
+}
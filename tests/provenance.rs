@@ -0,0 +1,119 @@
+use hyperion::{LSystem, LSystemBuilder, Provenance, Role, Rule, SampleOptions, State};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Traced {
+    A,
+    B,
+    F,
+    Cut,
+    Push,
+    Pop,
+}
+
+fn role(token: &Traced) -> Role {
+    match token {
+        Traced::Push => Role::Push,
+        Traced::Pop => Role::Pop,
+        Traced::Cut => Role::Cut,
+        _ => Role::Symbol,
+    }
+}
+
+fn produced(rule: usize, generation: usize, parent: usize) -> Provenance {
+    Provenance {
+        rule: Some(rule),
+        generation,
+        parent: Some(parent),
+    }
+}
+
+fn sample_with_provenance(
+    lsys: &LSystem<Traced>,
+    generation: usize,
+) -> (State<Traced>, Vec<Provenance>) {
+    let mut provenance = Vec::new();
+    let options = SampleOptions {
+        provenance: Some(&mut provenance),
+        ..Default::default()
+    };
+    let state = lsys.sample_with(generation, options).unwrap();
+    (state, provenance)
+}
+
+#[test]
+fn axiom() {
+    use Traced::*;
+    let lsys = LSystemBuilder::new([A, B]).rule(Rule::new(A, [A, B])).build();
+    let (state, provenance) = sample_with_provenance(&lsys, 0);
+    assert_eq!(state, State::new([A, B]));
+    assert_eq!(provenance, vec![Provenance::default(); 2]);
+}
+
+#[test]
+fn rules_and_parents() {
+    use Traced::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    let (state, provenance) = sample_with_provenance(&lsys, 2);
+    assert_eq!(state, lsys.sample(2));
+    assert_eq!(
+        provenance,
+        vec![produced(0, 2, 0), produced(0, 2, 0), produced(1, 2, 1)]
+    );
+}
+
+#[test]
+fn unchanged_modules_keep_their_origin() {
+    use Traced::*;
+    let lsys = LSystemBuilder::new([A]).rule(Rule::new(A, [F, A])).build();
+    let (state, provenance) = sample_with_provenance(&lsys, 3);
+    assert_eq!(state, State::new([F, F, F, A]));
+    assert_eq!(
+        provenance,
+        vec![
+            produced(0, 1, 0),
+            produced(0, 2, 1),
+            produced(0, 3, 2),
+            produced(0, 3, 2),
+        ]
+    );
+}
+
+#[test]
+fn cut_and_decomposition() {
+    use Traced::*;
+    let lsys = LSystemBuilder::new([F, Push, A, F, Pop, B])
+        .rule(Rule::new(A, [Cut]))
+        .decomposition(Rule::new(B, [F, F]))
+        .roles(role)
+        .build();
+    let (state, provenance) = sample_with_provenance(&lsys, 1);
+    assert_eq!(state, State::new([F, Push, Pop, F, F]));
+    let unchanged = |parent| Provenance {
+        parent: Some(parent),
+        ..Default::default()
+    };
+    assert_eq!(
+        provenance,
+        vec![unchanged(0), unchanged(1), unchanged(4), unchanged(5), unchanged(6)]
+    );
+}
+
+#[test]
+fn step_with_provenance() {
+    use Traced::*;
+    let lsys = LSystemBuilder::new([A])
+        .rule(Rule::new(A, [A, B]))
+        .rule(Rule::new(B, [A]))
+        .build();
+    let (state, mut provenance) = sample_with_provenance(&lsys, 1);
+    let options = SampleOptions {
+        provenance: Some(&mut provenance),
+        ..Default::default()
+    };
+    let state = lsys.step_with(&state, 2, options).unwrap();
+    assert_eq!((state, provenance), sample_with_provenance(&lsys, 2));
+}
@@ -1,9 +1,10 @@
-use hyperion::{LSystemBuilder, Module, Rule};
+use hyperion::{LSystemBuilder, Module, Rule, SampleOptions, Selection, State, Value};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Stochastic {
     A,
     B,
+    C,
 }
 
 #[test]
@@ -11,9 +12,132 @@ fn simple() {
     use Stochastic::*;
     let lsys = LSystemBuilder::new([A, A, A, A, A])
         .rule(Rule::new(A, [B]).with_probability(0.33))
+        .selection(Selection::Sequential)
+        .seed(2)
         .build();
 
     let output = lsys.sample(1);
     assert!(output.contains(&Module::new(B)));
     assert!(output.contains(&Module::new(A)));
 }
+
+#[test]
+fn seeded() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A, A, A, A, A, A, A, A])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(B, [A, B]).with_probability(0.5))
+        .selection(Selection::Sequential)
+        .seed(42)
+        .build();
+
+    for generation in 0..6 {
+        assert_eq!(lsys.sample(generation), lsys.sample(generation));
+    }
+}
+
+#[test]
+fn sample_with_rng() {
+    use rand::{rngs::StdRng, SeedableRng};
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A, A, A, A, A, A, A, A])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .selection(Selection::Sequential)
+        .build();
+
+    let sample = |seed| {
+        let options = SampleOptions {
+            rng: Some(&mut StdRng::seed_from_u64(seed)),
+            ..Default::default()
+        };
+        lsys.sample_with(4, options).unwrap()
+    };
+    assert_eq!(sample(7), sample(7));
+}
+
+#[test]
+fn weighted() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A; 64])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(A, [C]).with_probability(0.5))
+        .seed(3)
+        .build();
+
+    let output = lsys.sample(1);
+    assert!(!output.contains(&Module::new(A)));
+    assert!(output.contains(&Module::new(B)));
+    assert!(output.contains(&Module::new(C)));
+}
+
+#[test]
+fn weighted_parameter_names() {
+    use Stochastic::*;
+    let module = |token, name: &str| Module::new(token).params(vec![Value::Var(name.into())]);
+    let lsys = LSystemBuilder::new(vec![Module::new(A).params(vec![Value::Num(1.0)]); 64])
+        .rule(Rule::new(module(A, "x"), [module(B, "x")]).with_probability(0.5))
+        .rule(Rule::new(module(A, "y"), [module(C, "y")]).with_probability(0.5))
+        .seed(3)
+        .build();
+
+    let output = lsys.sample(1);
+    assert!(output.contains(&Module::new(B).params(vec![Value::Num(1.0)])));
+    assert!(output.contains(&Module::new(C).params(vec![Value::Num(1.0)])));
+}
+
+#[test]
+fn weighted_respects_rule_priority() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A; 16])
+        .rule(Rule::new(A, [B]))
+        .rule(Rule::new(A, [C]).with_previous(Some(B)))
+        .build();
+
+    assert_eq!(lsys.sample(1), State::new([B; 16]));
+}
+
+#[test]
+fn seeded_generations() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A; 8])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(A, [A, C]).with_probability(0.5))
+        .seed(11)
+        .build();
+
+    for (generation, state) in lsys.generations().take(5).enumerate() {
+        assert_eq!(state, Ok(lsys.sample(generation)));
+    }
+}
+
+#[test]
+fn seeded_step() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A; 8])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(B, [A, C]).with_probability(0.5))
+        .selection(Selection::Sequential)
+        .seed(5)
+        .build();
+
+    for generation in 1..6 {
+        let previous = lsys.sample(generation - 1);
+        assert_eq!(lsys.step(&previous, generation), Ok(lsys.sample(generation)));
+    }
+}
+
+#[test]
+fn sequential() {
+    use Stochastic::*;
+    let lsys = LSystemBuilder::new([A; 64])
+        .rule(Rule::new(A, [B]).with_probability(0.5))
+        .rule(Rule::new(A, [C]).with_probability(0.5))
+        .selection(Selection::Sequential)
+        .seed(3)
+        .build();
+
+    let output = lsys.sample(1);
+    assert!(output.contains(&Module::new(A)));
+    assert!(output.contains(&Module::new(B)));
+    assert!(output.contains(&Module::new(C)));
+}
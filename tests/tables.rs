@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use hyperion::{EvalErrorKind, LSystemBuilder, Rule, Schedule, State};
+use pretty_assertions::assert_eq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Seasonal {
+    Bud,
+    Stem,
+    Flower,
+}
+
+fn builder() -> LSystemBuilder<Seasonal> {
+    use Seasonal::*;
+    LSystemBuilder::new([Bud])
+        .table_rule("grow", Rule::new(Bud, [Stem, Bud]))
+        .table_rule("flower", Rule::new(Bud, [Flower]))
+}
+
+#[test]
+fn fixed() {
+    use Seasonal::*;
+    let lsys = builder()
+        .schedule(Schedule::Fixed(vec!["grow".into(), "grow".into(), "flower".into()]))
+        .build();
+    assert_eq!(lsys.sample(2), State::new([Stem, Stem, Bud]));
+    assert_eq!(lsys.sample(3), State::new([Stem, Stem, Flower]));
+    assert_eq!(lsys.sample(4), State::new([Stem, Stem, Flower]));
+}
+
+#[test]
+fn generation() {
+    use Seasonal::*;
+    let lsys = builder()
+        .schedule(Schedule::Generation(|t| {
+            if t < 3 { "grow" } else { "flower" }.to_string()
+        }))
+        .build();
+    assert_eq!(lsys.sample(4), State::new([Stem, Stem, Flower]));
+}
+
+#[test]
+fn callback() {
+    use Seasonal::*;
+    let lsys = builder()
+        .schedule(Schedule::Callback(Arc::new(|_, state: &State<Seasonal>| {
+            if state.len() < 4 { "grow" } else { "flower" }.to_string()
+        })))
+        .build();
+    assert_eq!(lsys.sample(5), State::new([Stem, Stem, Stem, Flower]));
+}
+
+#[test]
+fn default_rules() {
+    use Seasonal::*;
+    let lsys = builder()
+        .rule(Rule::new(Bud, [Bud, Bud]))
+        .schedule(Schedule::Fixed(vec![]))
+        .build();
+    assert_eq!(lsys.sample(2), State::new([Bud, Bud, Bud, Bud]));
+}
+
+#[test]
+fn missing_table() {
+    use Seasonal::*;
+    let lsys = builder()
+        .rule(Rule::new(Bud, [Bud, Bud]))
+        .schedule(Schedule::Fixed(vec!["grow".into(), "winter".into()]))
+        .build();
+    assert_eq!(lsys.try_sample(1), Ok(State::new([Stem, Bud])));
+    let err = lsys.try_sample(2).unwrap_err();
+    assert_eq!(err.kind, EvalErrorKind::MissingTable("winter".into()));
+    assert_eq!(err.generation, Some(2));
+}
+
+#[test]
+fn expand_iter() {
+    use Seasonal::*;
+    let lsys = builder()
+        .schedule(Schedule::Fixed(vec!["grow".into(), "grow".into(), "flower".into()]))
+        .build();
+    let expanded = lsys.expand_iter(3).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(State::new(expanded), State::new([Stem, Stem, Flower]));
+}